@@ -0,0 +1,15 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+layout(push_constant) uniform clear_parameters {
+    vec4 color;
+    float depth;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = color;
+    gl_FragDepth = depth;
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec4 color;
+
+#include "view.glsl"
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    gl_Position = projection * view * vec4(position, 1.0);
+    f_color = color;
+}
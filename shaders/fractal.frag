@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+layout(location = 1) in vec3 f_normal;
+layout(location = 2) in vec2 f_uv;
+
+#include "lighting.glsl"
+#include "fractal.glsl"
+
+layout(set = 3, binding = 0) uniform material_parameters {
+    Fractal fractal;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    // glows on its own, so only the fog applies
+    vec3 color = fractal_color(fractal, f_uv);
+    f_color = vec4(mix(fog.color, color, fog_visibility(f_position)), 1.0);
+}
@@ -0,0 +1,51 @@
+// Mandelbrot and Julia sets, in double precision when FRACTAL_DOUBLE is defined
+
+#ifdef FRACTAL_DOUBLE
+#define real double
+#define real2 dvec2
+#else
+#define real float
+#define real2 vec2
+#endif
+
+struct Fractal {
+    // the point shown at uv (0.5, 0.5)
+    real2 center;
+    // the size of the area shown across uvs 0 to 1, negative to flip an axis
+    real2 extent;
+    // the constant of the Julia set
+    real2 julia_c;
+    // 0 Mandelbrot, 1 Julia
+    int kind;
+    int max_iterations;
+    float escape_radius;
+    // color = offset + amplitude * cos(2 pi (frequency * t + phase))
+    vec3 palette_offset;
+    vec3 palette_amplitude;
+    vec3 palette_frequency;
+    vec3 palette_phase;
+};
+
+vec3 fractal_color(Fractal fractal, vec2 uv) {
+    real2 position = fractal.center + (real2(uv) - 0.5) * fractal.extent;
+    real2 z = fractal.kind == 1 ? position : real2(0.0);
+    real2 c = fractal.kind == 1 ? fractal.julia_c : position;
+    real radius_squared = fractal.escape_radius * fractal.escape_radius;
+
+    int i;
+    for (i = 0; i < fractal.max_iterations; i++) {
+        z = real2(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        if (dot(z, z) > radius_squared) {
+            break;
+        }
+    }
+    if (i == fractal.max_iterations) {
+        return vec3(0.0);
+    }
+
+    // fractional iteration count, so the bands blend into each other
+    float smooth_i = float(i) + 1.0 - log2(log(float(length(z))) / log(fractal.escape_radius));
+    float t = smooth_i / float(fractal.max_iterations);
+    return fractal.palette_offset + fractal.palette_amplitude
+        * cos(6.28318530718 * (fractal.palette_frequency * t + fractal.palette_phase));
+}
@@ -0,0 +1,15 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+#include "fractal.glsl"
+
+layout(set = 0, binding = 0) uniform explorer_parameters {
+    Fractal fractal;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(fractal_color(fractal, f_uv), 1.0);
+}
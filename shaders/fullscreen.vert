@@ -0,0 +1,10 @@
+#version 450
+
+layout(location = 0) out vec2 f_uv;
+
+void main() {
+    // A single triangle large enough to cover the whole screen,
+    // with uvs running from (0, 0) to (1, 1) over the visible part
+    f_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(f_uv * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+layout(location = 1) in vec4 f_color;
+
+// sRGB textures, so sampling returns linear color
+layout(set = 0, binding = 0) uniform sampler2D image;
+
+layout(location = 0) out vec4 f_out;
+
+void main() {
+    f_out = f_color * texture(image, f_uv);
+}
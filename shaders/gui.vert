@@ -0,0 +1,26 @@
+#version 450
+
+// in points from the top left of the screen
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+// sRGB with premultiplied alpha
+layout(location = 2) in vec4 color;
+
+layout(push_constant) uniform gui_parameters {
+    vec2 screen_size;
+};
+
+layout(location = 0) out vec2 f_uv;
+layout(location = 1) out vec4 f_color;
+
+vec3 linear_from_srgb(vec3 srgb) {
+    vec3 lower = srgb / 12.92;
+    vec3 higher = pow((srgb + 0.055) / 1.055, vec3(2.4));
+    return mix(higher, lower, vec3(lessThan(srgb, vec3(0.04045))));
+}
+
+void main() {
+    gl_Position = vec4(position / screen_size * 2.0 - 1.0, 0.0, 1.0);
+    f_uv = uv;
+    f_color = vec4(linear_from_srgb(color.rgb), color.a);
+}
@@ -0,0 +1,12 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+
+#include "lighting.glsl"
+#include "toon.glsl"
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(mix(fog.color, outline_color, fog_visibility(f_position)), 1.0);
+}
@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+
+#include "view.glsl"
+#include "object.glsl"
+#include "toon.glsl"
+
+layout(location = 0) out vec3 f_position;
+
+void main() {
+    // pushed out along the normal, only the back faces are drawn, so the hull shows
+    // just past the silhouette of the object in front of it
+    vec3 world_normal = normalize(vec3(inverse(transpose(world)) * vec4(normal, 0.0)));
+    vec4 world_position = world * vec4(position, 1.0);
+    world_position.xyz += world_normal * outline_width;
+    gl_Position = projection * view * world_position;
+    f_position = vec3(world_position);
+}
@@ -0,0 +1,65 @@
+// Set 2, filled by the engine once per view, see `uniforms::LightingUniforms`
+
+struct Light {
+    vec3 position;
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct Fog {
+    vec3 color;
+    // 0 none, 1 linear, 2 exponential, 3 exponential squared
+    int mode;
+    float start;
+    float end;
+    float density;
+    // 0 disables height fog
+    float height_density;
+    float height_falloff;
+    float base_height;
+};
+
+layout(set = 2, binding = 0) uniform light_parameters {
+    vec3 view_position;
+    Light light;
+    Fog fog;
+};
+
+// screen-space ambient occlusion of the view, a single white pixel where there is none
+layout(set = 2, binding = 1) uniform sampler2D occlusion;
+
+// The occlusion of the ambient term at the fragment's pixel
+float ambient_occlusion() {
+    return texture(occlusion, gl_FragCoord.xy / vec2(textureSize(occlusion, 0))).r;
+}
+
+// The fraction of the surface's color that reaches the eye through the fog
+float fog_visibility(vec3 position) {
+    vec3 ray = position - view_position;
+    float distance = length(ray);
+
+    float visibility = 1.0;
+    if (fog.mode == 1) {
+        visibility = clamp((fog.end - distance) / max(fog.end - fog.start, 0.0001), 0.0, 1.0);
+    } else if (fog.mode == 2) {
+        visibility = exp(-fog.density * distance);
+    } else if (fog.mode == 3) {
+        float d = fog.density * distance;
+        visibility = exp(-d * d);
+    }
+
+    // density falling off exponentially with height, integrated along the ray
+    if (fog.height_density > 0.0) {
+        float falloff = max(fog.height_falloff, 0.0001);
+        float amount = fog.height_density * exp(-falloff * (view_position.y - fog.base_height));
+        float rise = falloff * ray.y;
+        if (abs(rise) > 0.0001) {
+            amount *= (1.0 - exp(-rise)) / rise * distance;
+        } else {
+            amount *= distance;
+        }
+        visibility *= exp(-amount);
+    }
+    return visibility;
+}
@@ -3,19 +3,16 @@
 layout(location = 0) in vec3 position;
 layout(location = 1) in vec3 normal;
 layout(location = 2) in vec2 uv;
+layout(location = 3) in vec4 tangent;
 
-layout(set = 0, binding = 0) uniform view_matrices {
-    mat4 view;
-    mat4 projection;
-};
-
-layout(set = 1, binding = 0) uniform world_matrix {
-    mat4 world;
-};
+#include "view.glsl"
+#include "object.glsl"
 
 layout(location = 0) out vec3 f_position;
 layout(location = 1) out vec3 f_normal;
 layout(location = 2) out vec2 f_uv;
+// world space tangent, bitangent and normal, for normal_mapping.glsl
+layout(location = 3) out mat3 f_tbn;
 
 void main() {
     vec4 world_position = world * vec4(position, 1.0);
@@ -23,4 +20,11 @@ void main() {
     f_position = vec3(world_position);
     f_normal = vec3(inverse(transpose(world)) * vec4(normal, 0.0));
     f_uv = uv;
-}
\ No newline at end of file
+
+    vec3 n = normalize(f_normal);
+    vec3 t = normalize(vec3(world * vec4(tangent.xyz, 0.0)));
+    // scaling can skew the tangent away from the normal
+    t = normalize(t - n * dot(n, t));
+    vec3 b = cross(n, t) * tangent.w;
+    f_tbn = mat3(t, b, n);
+}
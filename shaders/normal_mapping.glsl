@@ -0,0 +1,8 @@
+// Tangent space normal maps, with the basis built in `normal.vert`
+
+layout(location = 3) in mat3 f_tbn;
+
+// The world space normal for a texel of a normal map with +y up, as in glTF
+vec3 mapped_normal(vec3 texel) {
+    return normalize(f_tbn * (texel * 2.0 - 1.0));
+}
@@ -0,0 +1,4 @@
+// Set 1, filled by the engine for each object, see `uniforms::ObjectUniforms`
+layout(set = 1, binding = 0) uniform world_matrix {
+    mat4 world;
+};
@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) flat in uint f_object_id;
+
+layout(location = 0) out uint f_out;
+
+void main() {
+    f_out = f_object_id;
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+
+#include "view.glsl"
+
+layout(push_constant) uniform object_parameters {
+    mat4 world;
+    uint object_id;
+};
+
+layout(location = 0) flat out uint f_object_id;
+
+void main() {
+    gl_Position = projection * view * world * vec4(position, 1.0);
+    f_object_id = object_id;
+}
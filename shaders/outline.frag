@@ -0,0 +1,36 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+layout(set = 0, binding = 0) uniform sampler2D mask;
+
+layout(push_constant) uniform outline_parameters {
+    vec4 color;
+    int width;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    ivec2 size = textureSize(mask, 0);
+    ivec2 pixel = ivec2(f_uv * vec2(size));
+
+    // Only the pixels around the highlighted objects are colored, not the objects themselves
+    if (texelFetch(mask, pixel, 0).r > 0.5) {
+        discard;
+    }
+
+    for (int y = -width; y <= width; ++y) {
+        for (int x = -width; x <= width; ++x) {
+            if (x * x + y * y > width * width) {
+                continue;
+            }
+            ivec2 neighbour = clamp(pixel + ivec2(x, y), ivec2(0), size - 1);
+            if (texelFetch(mask, neighbour, 0).r > 0.5) {
+                f_color = color;
+                return;
+            }
+        }
+    }
+    discard;
+}
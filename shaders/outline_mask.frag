@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) flat in uint f_object_id;
+
+layout(location = 0) out float f_mask;
+
+void main() {
+    f_mask = 1.0;
+}
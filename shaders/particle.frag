@@ -0,0 +1,11 @@
+#version 450
+
+layout(location = 0) in vec2 f_corner;
+layout(location = 1) in vec4 f_color;
+
+layout(location = 0) out vec4 f_out;
+
+void main() {
+    float falloff = max(1.0 - dot(f_corner, f_corner), 0.0);
+    f_out = vec4(f_color.rgb, f_color.a * falloff);
+}
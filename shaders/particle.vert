@@ -0,0 +1,48 @@
+#version 450
+
+struct Particle {
+    vec4 position;
+    vec4 velocity;
+};
+
+#include "view.glsl"
+
+layout(set = 1, binding = 0) readonly buffer Particles {
+    Particle particles[];
+};
+
+layout(push_constant) uniform particle_appearance {
+    vec4 start_color;
+    vec4 end_color;
+    float size;
+};
+
+layout(location = 0) out vec2 f_corner;
+layout(location = 1) out vec4 f_color;
+
+const vec2 corners[6] = vec2[](
+    vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+    vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0)
+);
+
+void main() {
+    Particle particle = particles[gl_VertexIndex / 6];
+    vec2 corner = corners[gl_VertexIndex % 6];
+
+    if (particle.position.w <= 0.0) {
+        // outside the clip volume, so dead particles are never rasterized
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        f_corner = corner;
+        f_color = vec4(0.0);
+        return;
+    }
+
+    // billboard facing the camera
+    vec4 view_position = view * vec4(particle.position.xyz, 1.0);
+    view_position.xy += corner * size;
+    gl_Position = projection * view_position;
+
+    float age = 1.0 - particle.position.w / particle.velocity.w;
+    f_corner = corner;
+    f_color = mix(start_color, end_color, age);
+}
@@ -0,0 +1,102 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+layout(location = 1) in vec3 f_normal;
+layout(location = 2) in vec2 f_uv;
+
+#include "lighting.glsl"
+#include "normal_mapping.glsl"
+
+const float PI = 3.14159265359;
+
+// each factor multiplies its map, which is a single white pixel for constant parameters
+struct Material {
+    vec4 base_color;
+    vec3 emissive;
+    float metallic;
+    float roughness;
+    float occlusion;
+    float alpha_cutoff;
+    // 1 when blended as premultiplied, the result is then multiplied by alpha
+    int premultiply;
+};
+
+layout(set = 3, binding = 0) uniform material_parameters {
+    Material material;
+};
+
+layout(set = 3, binding = 1) uniform sampler2D base_color_map;
+// channels follow glTF, so one packed occlusion-roughness-metallic map can serve all three
+layout(set = 3, binding = 2) uniform sampler2D metallic_map;
+layout(set = 3, binding = 3) uniform sampler2D roughness_map;
+layout(set = 3, binding = 4) uniform sampler2D emissive_map;
+layout(set = 3, binding = 5) uniform sampler2D occlusion_map;
+layout(set = 3, binding = 6) uniform sampler2D normal_map;
+
+layout(location = 0) out vec4 f_color;
+
+// Trowbridge-Reitz GGX normal distribution
+float distribution_ggx(float n_dot_h, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denominator = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denominator * denominator);
+}
+
+// Smith's method with Schlick-GGX for both the light and view directions
+float geometry_smith(float n_dot_v, float n_dot_l, float roughness) {
+    float r = roughness + 1.0;
+    float k = r * r / 8.0;
+    float view = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    float light = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return view * light;
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+void main() {
+    vec4 base_color = material.base_color * texture(base_color_map, f_uv);
+    if (base_color.a < material.alpha_cutoff) {
+        discard;
+    }
+    float metallic = clamp(material.metallic * texture(metallic_map, f_uv).b, 0.0, 1.0);
+    // fully smooth surfaces make the highlight vanish
+    float roughness = clamp(material.roughness * texture(roughness_map, f_uv).g, 0.04, 1.0);
+    vec3 emissive = material.emissive * texture(emissive_map, f_uv).rgb;
+    float occlusion = material.occlusion * texture(occlusion_map, f_uv).r;
+
+    vec3 albedo = base_color.rgb;
+    vec3 n = mapped_normal(texture(normal_map, f_uv).rgb);
+    vec3 v = normalize(view_position - f_position);
+    vec3 l = normalize(light.position - f_position);
+    vec3 h = normalize(v + l);
+
+    float n_dot_v = max(dot(n, v), 0.0001);
+    float n_dot_l = max(dot(n, l), 0.0);
+    float n_dot_h = max(dot(n, h), 0.0);
+
+    // dielectrics reflect about 4% head on, metals tint the reflection with their color
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec3 fresnel = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    float d = distribution_ggx(n_dot_h, roughness);
+    float g = geometry_smith(n_dot_v, n_dot_l, roughness);
+    vec3 specular = d * g * fresnel / (4.0 * n_dot_v * max(n_dot_l, 0.0001));
+
+    // metals have no diffuse reflection
+    vec3 k_diffuse = (1.0 - fresnel) * (1.0 - metallic);
+    // the light's diffuse color is the irradiance facing it, like Phong's, so a white
+    // matte surface comes out as bright under both
+    vec3 radiance = PI * light.diffuse;
+    vec3 direct = (k_diffuse * albedo / PI + specular) * radiance * n_dot_l;
+
+    vec3 ambient = ambient_occlusion() * occlusion * light.ambient * albedo;
+
+    vec3 result = ambient + direct + emissive;
+    result = mix(fog.color, result, fog_visibility(f_position));
+    if (material.premultiply == 1) {
+        result *= base_color.a;
+    }
+    f_color = vec4(result, base_color.a);
+}
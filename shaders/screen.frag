@@ -0,0 +1,19 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+layout(location = 1) in vec3 f_normal;
+layout(location = 2) in vec2 f_uv;
+
+layout(set = 3, binding = 0) uniform sampler2D screen;
+layout(set = 3, binding = 1) uniform screen_parameters {
+    vec3 tint;
+    vec2 uv_scale;
+    vec2 uv_offset;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec2 uv = f_uv * uv_scale + uv_offset;
+    f_color = vec4(texture(screen, uv).rgb * tint, 1.0);
+}
@@ -4,23 +4,17 @@ layout(location = 0) in vec3 f_position;
 layout(location = 1) in vec3 f_normal;
 layout(location = 2) in vec2 f_uv;
 
-struct Light {
-    vec3 position;
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-};
+#include "lighting.glsl"
 
 struct Material {
     vec3 ambient;
     vec3 diffuse;
     vec3 specular;
     float shininess;
-};
-
-layout(set = 2, binding = 0) uniform light_parameters {
-    vec3 view_position;
-    Light light;
+    float alpha;
+    float alpha_cutoff;
+    // 1 when blended as premultiplied, the result is then multiplied by alpha
+    int premultiply;
 };
 
 layout(set = 3, binding = 0) uniform material_parameters {
@@ -30,8 +24,12 @@ layout(set = 3, binding = 0) uniform material_parameters {
 layout(location = 0) out vec4 f_color;
 
 void main() {
+    if (material.alpha < material.alpha_cutoff) {
+        discard;
+    }
+
     // ambient
-    vec3 ambient = light.ambient * material.ambient;
+    vec3 ambient = ambient_occlusion() * light.ambient * material.ambient;
 
     // diffuse
     vec3 norm = normalize(f_normal);
@@ -46,5 +44,9 @@ void main() {
     vec3 specular = spec * light.specular * material.specular;
 
     vec3 result = ambient + diffuse + specular;
-    f_color = vec4(result, 1.0);
+    result = mix(fog.color, result, fog_visibility(f_position));
+    if (material.premultiply == 1) {
+        result *= material.alpha;
+    }
+    f_color = vec4(result, material.alpha);
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+#include "view.glsl"
+
+layout(set = 1, binding = 0) uniform sampler2D depth;
+layout(set = 1, binding = 1) uniform sampler2D normals;
+
+layout(push_constant) uniform ssao_parameters {
+    // origin and size of the view in pixels
+    vec4 viewport;
+    float radius;
+    float intensity;
+    int sample_count;
+    float clear_depth;
+};
+
+layout(location = 0) out float f_occlusion;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+ivec2 pixel_at(vec2 uv) {
+    ivec2 last = ivec2(viewport.xy + viewport.zw) - 1;
+    return clamp(ivec2(viewport.xy + uv * viewport.zw), ivec2(viewport.xy), last);
+}
+
+vec3 view_position(vec2 uv, float d, mat4 inverse_projection) {
+    vec4 position = inverse_projection * vec4(uv * 2.0 - 1.0, d, 1.0);
+    return position.xyz / position.w;
+}
+
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    float d = texelFetch(depth, pixel, 0).r;
+    if (d == clear_depth) {
+        f_occlusion = 1.0;
+        return;
+    }
+
+    mat4 inverse_projection = inverse(projection);
+    vec3 position = view_position(f_uv, d, inverse_projection);
+    vec3 normal = normalize(texelFetch(normals, pixel, 0).xyz);
+
+    // A frame around the normal, rotated randomly per pixel, the blur hides the noise
+    vec3 random = vec3(hash(gl_FragCoord.xy), hash(gl_FragCoord.yx + 17.0), 0.0) * 2.0 - 1.0;
+    vec3 tangent = random - normal * dot(random, normal);
+    if (length(tangent) < 0.0001) {
+        tangent = cross(normal, abs(normal.x) < 0.9 ? vec3(1.0, 0.0, 0.0) : vec3(0.0, 1.0, 0.0));
+    }
+    tangent = normalize(tangent);
+    mat3 tbn = mat3(tangent, cross(normal, tangent), normal);
+
+    float occluded = 0.0;
+    for (int i = 0; i < sample_count; ++i) {
+        // points in the hemisphere around the normal, more of them close to the surface
+        vec3 direction = normalize(vec3(
+            hash(vec2(i, 0.0)) * 2.0 - 1.0,
+            hash(vec2(i, 1.0)) * 2.0 - 1.0,
+            hash(vec2(i, 2.0)) + 0.05));
+        float scale = float(i + 1) / float(sample_count);
+        scale = mix(0.1, 1.0, scale * scale);
+        vec3 sample_position = position + tbn * direction * radius * scale;
+
+        vec4 projected = projection * vec4(sample_position, 1.0);
+        vec2 uv = projected.xy / projected.w * 0.5 + 0.5;
+        if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+            continue;
+        }
+        float sample_depth = texelFetch(depth, pixel_at(uv), 0).r;
+        if (sample_depth == clear_depth) {
+            continue;
+        }
+
+        // the camera looks down -z, so a surface in front of the sample has a greater z
+        vec3 scene = view_position(uv, sample_depth, inverse_projection);
+        float range = smoothstep(0.0, 1.0, radius / abs(position.z - scene.z));
+        occluded += (scene.z >= sample_position.z + 0.025 * radius ? 1.0 : 0.0) * range;
+    }
+
+    float occlusion = occluded / float(max(sample_count, 1));
+    f_occlusion = clamp(1.0 - intensity * occlusion, 0.0, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+layout(set = 0, binding = 0) uniform sampler2D occlusion;
+
+layout(location = 0) out float f_occlusion;
+
+void main() {
+    // A 4x4 box, enough to smooth out the noise of the per pixel rotation
+    ivec2 size = textureSize(occlusion, 0);
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    float sum = 0.0;
+    for (int y = -2; y < 2; ++y) {
+        for (int x = -2; x < 2; ++x) {
+            sum += texelFetch(occlusion, clamp(pixel + ivec2(x, y), ivec2(0), size - 1), 0).r;
+        }
+    }
+    f_occlusion = sum / 16.0;
+}
@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) in vec3 f_normal;
+
+layout(location = 0) out vec4 f_view_normal;
+
+void main() {
+    f_view_normal = vec4(normalize(f_normal), 0.0);
+}
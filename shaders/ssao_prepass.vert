@@ -0,0 +1,18 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+
+#include "view.glsl"
+
+layout(push_constant) uniform prepass_parameters {
+    mat4 world;
+};
+
+layout(location = 0) out vec3 f_normal;
+
+void main() {
+    mat4 view_world = view * world;
+    gl_Position = projection * view_world * vec4(position, 1.0);
+    f_normal = mat3(transpose(inverse(view_world))) * normal;
+}
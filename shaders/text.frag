@@ -0,0 +1,12 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+layout(location = 1) in vec4 f_color;
+
+layout(set = 0, binding = 0) uniform sampler2D atlas;
+
+layout(location = 0) out vec4 f_out;
+
+void main() {
+    f_out = vec4(f_color.rgb, f_color.a * texture(atlas, f_uv).r);
+}
@@ -0,0 +1,19 @@
+#version 450
+
+// in pixels from the top left of the screen
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+layout(push_constant) uniform text_parameters {
+    vec2 screen_size;
+};
+
+layout(location = 0) out vec2 f_uv;
+layout(location = 1) out vec4 f_color;
+
+void main() {
+    gl_Position = vec4(position / screen_size * 2.0 - 1.0, 0.0, 1.0);
+    f_uv = uv;
+    f_color = color;
+}
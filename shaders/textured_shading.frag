@@ -0,0 +1,61 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+layout(location = 1) in vec3 f_normal;
+layout(location = 2) in vec2 f_uv;
+
+#include "lighting.glsl"
+#include "normal_mapping.glsl"
+
+// the colors tint the maps
+struct Material {
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+    float alpha;
+    float alpha_cutoff;
+    // 1 when blended as premultiplied, the result is then multiplied by alpha
+    int premultiply;
+};
+
+layout(set = 3, binding = 0) uniform material_parameters {
+    Material material;
+};
+
+layout(set = 3, binding = 1) uniform sampler2D diffuse_map;
+layout(set = 3, binding = 2) uniform sampler2D specular_map;
+layout(set = 3, binding = 3) uniform sampler2D normal_map;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 diffuse_color = texture(diffuse_map, f_uv);
+    float alpha = material.alpha * diffuse_color.a;
+    if (alpha < material.alpha_cutoff) {
+        discard;
+    }
+    vec3 specular_color = texture(specular_map, f_uv).rgb;
+
+    // ambient
+    vec3 ambient = ambient_occlusion() * light.ambient * material.ambient * diffuse_color.rgb;
+
+    // diffuse
+    vec3 norm = mapped_normal(texture(normal_map, f_uv).rgb);
+    vec3 light_direction = normalize(light.position - f_position);
+    float diff = max(dot(norm, light_direction), 0.0);
+    vec3 diffuse = diff * light.diffuse * material.diffuse * diffuse_color.rgb;
+
+    // specular
+    vec3 view_direction = normalize(view_position - f_position);
+    vec3 reflect_direction = reflect(-light_direction, norm);
+    float spec = pow(max(dot(view_direction, reflect_direction), 0.0), material.shininess);
+    vec3 specular = spec * light.specular * material.specular * specular_color;
+
+    vec3 result = ambient + diffuse + specular;
+    result = mix(fog.color, result, fog_visibility(f_position));
+    if (material.premultiply == 1) {
+        result *= alpha;
+    }
+    f_color = vec4(result, alpha);
+}
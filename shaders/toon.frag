@@ -0,0 +1,39 @@
+#version 450
+
+layout(location = 0) in vec3 f_position;
+layout(location = 1) in vec3 f_normal;
+layout(location = 2) in vec2 f_uv;
+
+#include "lighting.glsl"
+#include "toon.glsl"
+
+// read across its width at the diffuse term, from facing away at 0 to facing the light at 1
+layout(set = 3, binding = 1) uniform sampler2D ramp;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec3 norm = normalize(f_normal);
+    vec3 light_direction = normalize(light.position - f_position);
+    vec3 view_direction = normalize(view_position - f_position);
+
+    vec3 ambient = ambient_occlusion() * light.ambient * color;
+
+    float diff = max(dot(norm, light_direction), 0.0);
+    vec3 shade;
+    if (bands > 0) {
+        shade = vec3(ceil(diff * float(bands)) / float(bands));
+    } else {
+        shade = texture(ramp, vec2(diff, 0.5)).rgb;
+    }
+    vec3 diffuse = shade * light.diffuse * color;
+
+    // a hard edged band along the silhouette
+    float edge = 1.0 - max(dot(norm, view_direction), 0.0);
+    float rim = step(1.0 - rim_width, edge) * step(0.0001, rim_width);
+    vec3 rim_light = rim * light.diffuse * rim_color;
+
+    vec3 result = ambient + diffuse + rim_light;
+    result = mix(fog.color, result, fog_visibility(f_position));
+    f_color = vec4(result, 1.0);
+}
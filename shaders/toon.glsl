@@ -0,0 +1,13 @@
+// Set 3 of the toon material, shared by its shading and its outline
+
+layout(set = 3, binding = 0) uniform toon_parameters {
+    vec3 color;
+    // the number of flat steps the diffuse term is rounded up to, 0 reads the ramp instead
+    int bands;
+    vec3 rim_color;
+    // how far in from the silhouette the rim reaches, 0 to 1
+    float rim_width;
+    vec3 outline_color;
+    // in world units
+    float outline_width;
+};
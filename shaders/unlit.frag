@@ -0,0 +1,28 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+layout(location = 1) in vec4 f_vertex_color;
+
+// no lighting.glsl, the color is drawn as it is, without light or fog
+
+layout(set = 3, binding = 0) uniform unlit_parameters {
+    vec4 color;
+    // 1 to multiply in the vertex colors
+    int vertex_colors;
+    float alpha_cutoff;
+};
+
+layout(set = 3, binding = 1) uniform sampler2D color_map;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 result = color * texture(color_map, f_uv);
+    if (vertex_colors == 1) {
+        result *= f_vertex_color;
+    }
+    if (result.a < alpha_cutoff) {
+        discard;
+    }
+    f_color = result;
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 2) in vec2 uv;
+layout(location = 4) in vec4 color;
+
+#include "view.glsl"
+#include "object.glsl"
+
+layout(location = 0) out vec2 f_uv;
+layout(location = 1) out vec4 f_vertex_color;
+
+void main() {
+    gl_Position = projection * view * world * vec4(position, 1.0);
+    f_uv = uv;
+    f_vertex_color = color;
+}
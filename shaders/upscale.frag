@@ -0,0 +1,29 @@
+#version 450
+
+layout(location = 0) in vec2 f_uv;
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(push_constant) uniform upscale_parameters {
+    float sharpness;
+};
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 center = texture(scene, f_uv);
+    if (sharpness <= 0.0) {
+        f_color = center;
+        return;
+    }
+
+    // Unsharp mask over the four direct neighbours of the source texel
+    vec2 texel = 1.0 / vec2(textureSize(scene, 0));
+    vec3 neighbours = texture(scene, f_uv + vec2(texel.x, 0.0)).rgb
+        + texture(scene, f_uv - vec2(texel.x, 0.0)).rgb
+        + texture(scene, f_uv + vec2(0.0, texel.y)).rgb
+        + texture(scene, f_uv - vec2(0.0, texel.y)).rgb;
+
+    vec3 sharpened = center.rgb + sharpness * (4.0 * center.rgb - neighbours);
+    f_color = vec4(clamp(sharpened, 0.0, 1.0), center.a);
+}
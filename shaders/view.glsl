@@ -0,0 +1,5 @@
+// Set 0, filled by the engine once per view, see `uniforms::ViewUniforms`
+layout(set = 0, binding = 0) uniform view_matrices {
+    mat4 view;
+    mat4 projection;
+};
@@ -1,5 +1,6 @@
 use nalgebra_glm as glm;
 
+use crate::depth::DepthConfig;
 use crate::utility::{clamp, radians};
 
 pub enum CameraMoveDirection {
@@ -47,13 +48,15 @@ impl Default for Camera {
 
 impl Camera {
     pub fn new(position: glm::Vec3, up: glm::Vec3, yaw: f32, pitch: f32) -> Self {
-        let mut camera = Camera::default();
-        camera.position = position;
-        camera.world_up = up;
+        let mut camera = Camera {
+            position,
+            world_up: up,
+            yaw,
+            pitch,
+            ..Camera::default()
+        };
         // Fix for vulkan's reversed y axis
         camera.world_up[1] *= -1.0;
-        camera.yaw = yaw;
-        camera.pitch = pitch;
         camera.update_camera_vectors();
         camera
     }
@@ -62,10 +65,47 @@ impl Camera {
         self.position
     }
 
+    /// In degrees
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    /// In degrees
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    pub fn forward(&self) -> glm::Vec3 {
+        self.forward
+    }
+
+    pub fn right(&self) -> glm::Vec3 {
+        self.right
+    }
+
+    /// Points down in world space, see the fix in `Camera::new`
+    pub fn up(&self) -> glm::Vec3 {
+        self.up
+    }
+
     pub fn get_view_matrix(&self) -> glm::Mat4 {
         glm::look_at(&self.position, &(self.position + self.forward), &self.up)
     }
 
+    pub fn projection_matrix(&self, aspect_ratio: f32, depth: &DepthConfig) -> glm::Mat4 {
+        let fovy = self.zoom();
+        match (depth.reverse_z, depth.far) {
+            (false, Some(far)) => glm::perspective_rh_zo(aspect_ratio, fovy, depth.near, far),
+            (false, None) => glm::infinite_perspective_rh_zo(aspect_ratio, fovy, depth.near),
+            (true, Some(far)) => {
+                glm::reversed_perspective_rh_zo(aspect_ratio, fovy, depth.near, far)
+            }
+            (true, None) => {
+                glm::reversed_infinite_perspective_rh_zo(aspect_ratio, fovy, depth.near)
+            }
+        }
+    }
+
     pub fn move_camera(&mut self, direction: CameraMoveDirection, delta_time: f32) {
         let velocity = self.speed * delta_time;
         match direction {
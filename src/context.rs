@@ -56,9 +56,12 @@ pub struct RenderContext {
 }
 
 impl RenderContext {
+    /// `optional_features` are enabled where the device supports them, check
+    /// `Device::enabled_features` for which were
     pub fn new(
         app_info: Option<&ApplicationInfo>,
         required_features: &Features,
+        optional_features: &Features,
         instance_extensions: &InstanceExtensions,
         device_extensions: &DeviceExtensions,
         layers: Vec<&str>,
@@ -66,7 +69,7 @@ impl RenderContext {
         let instance = Instance::new(app_info, instance_extensions, layers)?;
         let (physical_device_index, device, mut queues) = {
             let physical_device = PhysicalDevice::enumerate(&instance)
-                .find(|p| p.supported_features().superset_of(&required_features))
+                .find(|p| p.supported_features().superset_of(required_features))
                 .ok_or(RenderContextError::NoSupportedDevice)?;
 
             // TODO how to choose queue families?
@@ -75,9 +78,17 @@ impl RenderContext {
                 .find(|&q| q.supports_graphics())
                 .expect("couldn't find a graphical queue family");
 
+            // the supported features that are either required or optional
+            let supported = physical_device.supported_features();
+            let features = supported.difference(
+                &supported
+                    .difference(required_features)
+                    .difference(optional_features),
+            );
+
             let (device, queues) = Device::new(
                 physical_device,
-                &Features::none(),
+                &features,
                 device_extensions,
                 [(queue_family, 0.5)].iter().cloned(),
             )?;
@@ -97,7 +108,7 @@ impl RenderContext {
         self.instance.clone()
     }
 
-    pub fn physical_device(&self) -> PhysicalDevice {
+    pub fn physical_device(&self) -> PhysicalDevice<'_> {
         PhysicalDevice::from_index(&self.instance, self.physical_device_index)
             .expect("Physical device disappeared, this shouldn't happen")
     }
@@ -1,25 +1,44 @@
 use std::error;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Instant;
 
 use crate::camera::{Camera, CameraMoveDirection};
 use crate::context::RenderContext;
-use crate::input::InputHandler;
-use crate::material::phong::Phong;
-use crate::material::Material;
+use crate::depth::DepthConfig;
+use crate::fog::{Fog, FogMode};
+use crate::gui::{inspector, Gui};
+use crate::input::{InputEvent, InputHandler};
+use crate::light::PointLight;
+use crate::material::definition;
+use crate::material::fractal::{Fractal, FractalKind, FractalParameters};
+use crate::material::phong::{Phong, PhongParameters};
+use crate::material::screen::{Screen, ScreenParameters};
+use crate::material::textured_phong::{TexturedPhong, TexturedPhongParameters};
+use crate::material::unlit::{Unlit, UnlitParameters};
 use crate::mesh::cube::Cube;
-use crate::renderer::Renderer;
+use crate::mesh::sphere::Sphere;
+use crate::particles::{EmitterParameters, ParticleEmitter, ParticleSystem};
+use crate::render_queue::RenderQueue;
+use crate::renderer::{OffscreenView, Renderer, SceneView};
+use crate::resolution::ResolutionController;
 use crate::scene::{SceneGraph, SceneObject};
+use crate::stats::StatsOverlay;
+use crate::texture::{ColorSpace, ImageData, SamplerParameters, Texture};
+use crate::uniforms::{LightingUniforms, ViewUniforms};
+use crate::view::{CameraView, ViewportRect};
 use crate::window::RenderWindow;
 
 use nalgebra_glm as glm;
 use vulkano::buffer::cpu_pool::CpuBufferPool;
 use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
 use vulkano::device::{DeviceExtensions, Features};
+use vulkano::image::ImageViewAccess;
 use vulkano::swapchain::Surface;
 use vulkano::sync::GpuFuture;
 
+use winit::event::MouseButton;
 use winit::window::Window;
 
 pub struct Controller {
@@ -35,6 +54,12 @@ impl Controller {
         let context = RenderContext::new(
             Some(&app_info),
             &Features::none(),
+            // for the fractal explorer's deep zoom, and for anisotropic filtering where asked
+            &Features {
+                shader_f3264: true,
+                sampler_anisotropy: true,
+                ..Features::none()
+            },
             &vulkano_win::required_extensions(),
             &DeviceExtensions {
                 khr_storage_buffer_storage_class: true,
@@ -76,27 +101,163 @@ impl Controller {
     fn run_internal(self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
         let queue = self.context.queue();
 
-        let mut renderer = Renderer::new(self.context.clone(), self.surface.clone())?;
+        let mut renderer = Renderer::new(
+            self.context.clone(),
+            self.surface.clone(),
+            DepthConfig::reverse_z_infinite(0.1),
+        )?;
+        // materials of the same kind and blend mode share one pipeline from here
+        let material_pipelines = renderer.material_pipelines();
+
+        let phong_parameters1 = PhongParameters {
+            ambient: glm::vec3(0.1, 0.4, 0.8),
+            diffuse: glm::vec3(0.1, 0.4, 0.8),
+            specular: glm::vec3(1.0, 1.0, 1.0),
+            shininess: 50.0f32,
+            ..PhongParameters::default()
+        };
+        let (phong_material1, future1) =
+            Phong::new(phong_parameters1, &material_pipelines, queue.clone())?;
+
+        let phong_parameters2 = PhongParameters {
+            ambient: glm::vec3(0.8, 0.4, 0.1),
+            diffuse: glm::vec3(0.8, 0.4, 0.1),
+            specular: glm::vec3(1.0, 1.0, 1.0),
+            shininess: 20.0f32,
+            ..PhongParameters::default()
+        };
+        let (phong_material2, future2) =
+            Phong::new(phong_parameters2, &material_pipelines, queue.clone())?;
 
-        let (phong_material1, future1) = Phong::new(
-            glm::vec3(0.1, 0.4, 0.8),
-            glm::vec3(0.1, 0.4, 0.8),
-            glm::vec3(1.0, 1.0, 1.0),
-            50.0f32,
-            self.context.device(),
+        let (gold_material, future5) = definition::load_material(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("materials/gold.toml"),
+            &material_pipelines,
             queue.clone(),
-            renderer.render_pass(),
         )?;
 
-        let (phong_material2, future2) = Phong::new(
-            glm::vec3(0.8, 0.4, 0.1),
-            glm::vec3(0.8, 0.4, 0.1),
-            glm::vec3(1.0, 1.0, 1.0),
-            20.0f32,
+        // a checkerboard whose dark squares are also less shiny and sunken
+        let size = 64;
+        let is_light = |x: u32, y: u32| ((x % size) / 8 + (y % size) / 8).is_multiple_of(2);
+        let checkerboard = |light: u8, dark: u8| {
+            let pixels = (0..size * size)
+                .flat_map(|i| {
+                    let value = if is_light(i % size, i / size) {
+                        light
+                    } else {
+                        dark
+                    };
+                    vec![value, value, value, 255]
+                })
+                .collect();
+            ImageData {
+                pixels,
+                dimensions: [size, size],
+            }
+        };
+        let bumps = {
+            let height = |x: u32, y: u32| if is_light(x, y) { 1.0 } else { 0.0 };
+            let pixels = (0..size * size)
+                .flat_map(|i| {
+                    let (x, y) = (i % size + size, i / size + size);
+                    // +y points up the image, towards decreasing rows
+                    let normal = glm::normalize(&glm::vec3(
+                        height(x - 1, y) - height(x + 1, y),
+                        height(x, y + 1) - height(x, y - 1),
+                        1.0,
+                    ));
+                    let encode = |value: f32| ((value * 0.5 + 0.5) * 255.0).round() as u8;
+                    vec![encode(normal.x), encode(normal.y), encode(normal.z), 255]
+                })
+                .collect();
+            ImageData {
+                pixels,
+                dimensions: [size, size],
+            }
+        };
+        let (diffuse_map, future6) = Texture::new(
+            &checkerboard(230, 40),
+            ColorSpace::Srgb,
+            &SamplerParameters::default(),
+            queue.clone(),
+        )?;
+        let (specular_map, future7) = Texture::new(
+            &checkerboard(255, 60),
+            ColorSpace::Linear,
+            &SamplerParameters::default(),
+            queue.clone(),
+        )?;
+        let (normal_map, future9) = Texture::new(
+            &bumps,
+            ColorSpace::Linear,
+            &SamplerParameters::default(),
+            queue.clone(),
+        )?;
+        let (checkered_material, future8) = TexturedPhong::new(
+            TexturedPhongParameters {
+                phong: PhongParameters::default(),
+                diffuse_map,
+                specular_map,
+                normal_map: Some(normal_map),
+            },
+            &material_pipelines,
+            queue.clone(),
+        )?;
+
+        let (julia_material, future10) = Fractal::new(
+            FractalParameters {
+                kind: FractalKind::Julia {
+                    c: glm::vec2(-0.8, 0.156),
+                },
+                center: glm::vec2(0.0, 0.0),
+                zoom: 0.8,
+                ..FractalParameters::default()
+            },
+            &material_pipelines,
+            queue.clone(),
+        )?;
+
+        let (vertex_color_material, future11) = definition::load_material(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("materials/vertex_colors.toml"),
+            &material_pipelines,
+            queue.clone(),
+        )?;
+        let (light_marker_material, future12) = Unlit::new(
+            UnlitParameters {
+                color: glm::vec4(1.0, 0.9, 0.5, 1.0),
+                ..UnlitParameters::default()
+            },
+            &material_pipelines,
+            queue.clone(),
+        )?;
+
+        let (toon_material, future13) = definition::load_material(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("materials/toon.toml"),
+            &material_pipelines,
+            queue.clone(),
+        )?;
+
+        // a monitor showing what a security camera sees
+        let monitor_target = renderer.create_render_target([512, 512])?;
+        let (screen_material, future3) = Screen::new(
+            ScreenParameters::default(),
+            monitor_target.clone(),
+            &material_pipelines,
+            queue.clone(),
+        )?;
+        let security_camera = Camera::new(
+            glm::vec3(4.0, 3.0, 4.0),
+            glm::vec3(0.0, 1.0, 0.0),
+            -135.0f32,
+            -28.0f32,
+        );
+
+        let particle_system = ParticleSystem::new(
             self.context.device(),
-            queue,
             renderer.render_pass(),
+            renderer.depth_config(),
         )?;
+        let (emitter, future4) =
+            ParticleEmitter::new(particle_system, EmitterParameters::default(), queue.clone())?;
 
         let cube_mesh = Cube::new(self.context.device());
         let scene_object1 = SceneObject::new(
@@ -104,60 +265,299 @@ impl Controller {
             phong_material1.clone(),
             cube_mesh.clone(),
         );
-        let scene_object2 = SceneObject::new(self.context.device(), phong_material2, cube_mesh);
+        let scene_object2 = SceneObject::new(
+            self.context.device(),
+            phong_material2.clone(),
+            cube_mesh.clone(),
+        );
 
-        let cube1 = SceneGraph::new(
+        // edited through the GUI
+        let phong_materials = [("Blue", phong_material1), ("Orange", phong_material2)];
+
+        let mut cube1 = SceneGraph::new(
             glm::translate(&glm::identity(), &glm::vec3(2.0, 0.0, 0.0)),
             Some(scene_object1),
             vec![],
         );
-        let cube2 = SceneGraph::new(
+        let mut cube2 = SceneGraph::new(
             glm::translate(&glm::identity(), &glm::vec3(-2.0, 0.0, 0.0)),
             Some(scene_object2),
             vec![],
         );
+        cube1.set_name("Blue cube");
+        cube2.set_name("Orange cube");
+
+        let mut gold_cube = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(0.0, 0.0, -2.0)),
+            Some(SceneObject::new(
+                self.context.device(),
+                gold_material,
+                cube_mesh.clone(),
+            )),
+            vec![],
+        );
+        gold_cube.set_name("Gold cube");
+
+        let mut checkered_cube = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(0.0, 2.0, -2.0)),
+            Some(SceneObject::new(
+                self.context.device(),
+                checkered_material,
+                cube_mesh.clone(),
+            )),
+            vec![],
+        );
+        checkered_cube.set_name("Checkered cube");
+
+        let mut julia_cube = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(-2.0, 2.0, -2.0)),
+            Some(SceneObject::new(
+                self.context.device(),
+                julia_material,
+                cube_mesh.clone(),
+            )),
+            vec![],
+        );
+        julia_cube.set_name("Julia cube");
+
+        let mut rgb_cube = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(2.0, 2.0, -2.0)),
+            Some(SceneObject::new(
+                self.context.device(),
+                vertex_color_material,
+                Cube::rgb(self.context.device()),
+            )),
+            vec![],
+        );
+        rgb_cube.set_name("RGB cube");
+
+        let mut toon_sphere = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(0.0, 0.0, 2.0)),
+            Some(SceneObject::new(
+                self.context.device(),
+                toon_material,
+                Sphere::new(self.context.device(), 32, 16),
+            )),
+            vec![],
+        );
+        toon_sphere.set_name("Toon sphere");
+
+        let mut fountain = SceneGraph::new(
+            glm::translate(&glm::identity(), &glm::vec3(0.0, -1.0, 0.0)),
+            None,
+            vec![],
+        );
+        fountain.set_name("Fountain");
+        fountain.set_emitter(emitter);
+
+        let mut monitor = SceneGraph::new(
+            glm::scale(
+                &glm::translate(&glm::identity(), &glm::vec3(0.0, 1.5, -4.0)),
+                &glm::vec3(2.0, 2.0, 0.1),
+            ),
+            Some(SceneObject::new(
+                self.context.device(),
+                screen_material,
+                cube_mesh.clone(),
+            )),
+            vec![],
+        );
+        monitor.set_name("Monitor");
 
         let mut scene_graph = SceneGraph::default();
+        scene_graph.set_name("Scene");
         scene_graph.add_child(cube1);
         scene_graph.add_child(cube2);
-
-        let mut camera = Camera::new(
-            glm::vec3(0.0, 0.0, 5.0),
-            glm::vec3(0.0, 1.0, 0.0),
-            -90.0f32,
-            0.0f32,
+        scene_graph.add_child(gold_cube);
+        scene_graph.add_child(checkered_cube);
+        scene_graph.add_child(julia_cube);
+        scene_graph.add_child(fountain);
+        scene_graph.add_child(monitor);
+        scene_graph.add_child(rgb_cube);
+        scene_graph.add_child(toon_sphere);
+
+        let mut light = PointLight {
+            position: glm::vec3(0.0, 1.1, 0.0),
+            ..PointLight::default()
+        };
+        // a small unlit cube showing where the light is, moved along with it
+        let light_marker_transform = |position: &glm::Vec3| {
+            glm::scale(
+                &glm::translate(&glm::identity(), position),
+                &glm::vec3(0.05, 0.05, 0.05),
+            )
+        };
+        let mut light_marker = SceneGraph::new(
+            light_marker_transform(&light.position),
+            Some(SceneObject::new(
+                self.context.device(),
+                light_marker_material,
+                cube_mesh.clone(),
+            )),
+            vec![],
         );
+        light_marker.set_name("Light");
+        let light_marker_index = scene_graph.get_children().len();
+        scene_graph.add_child(light_marker);
+
+        let [main_rect, overhead_rect] = ViewportRect::picture_in_picture(0.3, 0.02);
+        let mut camera_views = vec![
+            CameraView::new(
+                Camera::new(
+                    glm::vec3(0.0, 0.0, 5.0),
+                    glm::vec3(0.0, 1.0, 0.0),
+                    -90.0f32,
+                    0.0f32,
+                ),
+                main_rect,
+            ),
+            CameraView::new(
+                Camera::new(
+                    glm::vec3(0.0, 8.0, 0.0),
+                    glm::vec3(0.0, 1.0, 0.0),
+                    -90.0f32,
+                    -89.0f32,
+                ),
+                overhead_rect,
+            ),
+        ];
+        // the camera controlled by input
+        let mut active_camera = 0;
 
         // for timing
         let mut last_frame_time = Instant::now();
+        let mut resolution_controller = ResolutionController::new(1.0 / 60.0, 0.5, 1.0);
+        let mut stats_overlay = StatsOverlay::default();
+        let mut gui = Gui::default();
 
         // for uniforms
         let view_uniform_buffer_pool = CpuBufferPool::uniform_buffer(self.context.device());
         let lighting_uniform_buffer_pool = CpuBufferPool::uniform_buffer(self.context.device());
 
-        let mut aspect_ratio = 1280.0f32 / 1024.0f32;
+        let depth_config = renderer.depth_config();
+        let ambient_occlusion_sampler = renderer.ambient_occlusion().sampler();
+        let scene_layouts = renderer.scene_layouts();
+        // builds the view and lighting descriptor sets for a camera
+        let view_sets =
+            |camera: &Camera,
+             aspect_ratio: f32,
+             light: &PointLight,
+             fog: &Fog,
+             fog_color: glm::Vec3,
+             occlusion: Arc<dyn ImageViewAccess + Send + Sync>| {
+                let view_uniform_data = ViewUniforms::new(camera, aspect_ratio, &depth_config);
+
+                let sub_buffer_view_uniforms =
+                    view_uniform_buffer_pool.next(view_uniform_data).unwrap();
+
+                let view_set = Arc::new(
+                    PersistentDescriptorSet::start(scene_layouts.view())
+                        .add_buffer(sub_buffer_view_uniforms.clone())
+                        .unwrap()
+                        .build()
+                        .unwrap(),
+                );
+
+                let lighting_uniform_data =
+                    LightingUniforms::new(camera.position(), light, fog, fog_color);
+
+                let sub_buffer_lighting_uniforms = lighting_uniform_buffer_pool
+                    .next(lighting_uniform_data)
+                    .unwrap();
+
+                let lighting_set = Arc::new(
+                    PersistentDescriptorSet::start(scene_layouts.lighting())
+                        .add_buffer(sub_buffer_lighting_uniforms.clone())
+                        .unwrap()
+                        .add_sampled_image(occlusion, ambient_occlusion_sampler.clone())
+                        .unwrap()
+                        .build()
+                        .unwrap(),
+                );
+
+                (view_set, lighting_set)
+            };
 
-        let mut previous_frame_end = Some(future1.join(future2).boxed());
+        let mut selected: Option<usize> = None;
+        let mut ssao_parameters = renderer.ssao_parameters();
+        let mut fog = Fog {
+            mode: FogMode::Exponential { density: 0.03 },
+            height: None,
+        };
+        // shown over the whole window in place of the scene while `Some`
+        let mut fractal: Option<FractalParameters> = None;
+        let mut last_cursor_position = (0.0, 0.0);
+
+        let mut previous_frame_end = Some(
+            future1
+                .join(future2)
+                .join(future3)
+                .join(future4)
+                .join(future5)
+                .join(future6)
+                .join(future7)
+                .join(future8)
+                .join(future9)
+                .join(future10)
+                .join(future11)
+                .join(future12)
+                .join(future13)
+                .boxed(),
+        );
         loop {
-            let input = self
-                .input_handler
-                .lock()
-                .expect("could not lock input handler")
-                .poll();
+            let (input, input_events) = {
+                let mut input_handler = self
+                    .input_handler
+                    .lock()
+                    .expect("could not lock input handler");
+                (input_handler.poll(), input_handler.poll_events())
+            };
 
             if input.exiting {
                 break;
             }
 
-            if let Some(size) = input.resized {
-                aspect_ratio = size.width as f32 / size.height as f32;
+            if input.resized.is_some() {
                 renderer.resized();
             }
 
             let delta_time = last_frame_time.elapsed().as_secs_f32();
             last_frame_time = Instant::now();
 
-            if input.focused {
+            renderer.set_render_scale(resolution_controller.update(delta_time));
+            scene_graph.update_particles(delta_time);
+
+            stats_overlay.update(delta_time);
+            if input.toggle_stats {
+                stats_overlay.toggle();
+            }
+
+            if input.toggle_gui {
+                gui.toggle();
+            }
+            if input.toggle_fractal {
+                fractal = match fractal {
+                    Some(_) => None,
+                    None => Some(FractalParameters::default()),
+                };
+            }
+            // the cursor is released while the GUI or the fractal explorer is shown so it can be
+            // clicked and dragged
+            let cursor_released = gui.visible() || fractal.is_some();
+            if input.toggle_gui || input.toggle_fractal {
+                let window = self.surface.window();
+                window
+                    .set_cursor_grab(!cursor_released)
+                    .expect("Could not grab cursor!");
+                window.set_cursor_visible(cursor_released);
+            }
+
+            if input.next_camera {
+                active_camera = (active_camera + 1) % camera_views.len();
+            }
+
+            if input.focused && !gui.wants_keyboard_input() && fractal.is_none() {
+                let camera = &mut camera_views[active_camera].camera;
                 if input.move_forward_pressed {
                     camera.move_camera(CameraMoveDirection::FORWARD, delta_time);
                 }
@@ -176,102 +576,185 @@ impl Controller {
                 if input.move_down_pressed {
                     camera.move_camera(CameraMoveDirection::DOWN, delta_time);
                 }
+            }
+            if input.focused && !cursor_released {
+                let camera = &mut camera_views[active_camera].camera;
                 let (x_offset, y_offset) = input.cursor_offset;
                 camera.turn_camera(x_offset as f32, y_offset as f32);
                 camera.zoom_camera(input.mouse_wheel_delta as f32);
             }
 
-            let projection = glm::perspective(aspect_ratio, camera.zoom(), 0.1, 100.0);
+            if let Some(ref mut fractal) = fractal {
+                let size = self.surface.window().inner_size();
+                let dimensions = [size.width, size.height];
+                let cursor = [input.cursor_position.0, input.cursor_position.1];
+                if input.focused && !gui.wants_pointer_input() {
+                    if input.left_mouse_pressed {
+                        fractal.pan(
+                            [
+                                input.cursor_position.0 - last_cursor_position.0,
+                                input.cursor_position.1 - last_cursor_position.1,
+                            ],
+                            dimensions,
+                        );
+                    }
+                    if input.mouse_wheel_delta != 0.0 {
+                        fractal.zoom_at(1.2f64.powf(input.mouse_wheel_delta), cursor, dimensions);
+                    }
+                }
+            }
+            last_cursor_position = input.cursor_position;
+
+            if gui.visible() {
+                let window = self.surface.window();
+                let mut edited_material = None;
+                let output = gui.run(
+                    &input,
+                    &input_events,
+                    window.inner_size().into(),
+                    window.scale_factor() as f32,
+                    |context| {
+                        egui::Window::new("Scene graph").show(context, |ui| {
+                            inspector::scene_graph(ui, &mut scene_graph, selected);
+                        });
+                        egui::Window::new("Materials").show(context, |ui| {
+                            for (i, (name, material)) in phong_materials.iter().enumerate() {
+                                ui.collapsing(*name, |ui| {
+                                    let mut parameters = material.parameters();
+                                    if inspector::phong_parameters(ui, name, &mut parameters) {
+                                        edited_material = Some((i, parameters));
+                                    }
+                                });
+                            }
+                        });
+                        egui::Window::new("Light").show(context, |ui| {
+                            inspector::point_light(ui, &mut light);
+                        });
+                        egui::Window::new("Fog").show(context, |ui| {
+                            inspector::fog(ui, &mut fog);
+                        });
+                        egui::Window::new("Ambient occlusion").show(context, |ui| {
+                            inspector::ssao_parameters(ui, &mut ssao_parameters);
+                        });
+                        if let Some(ref mut fractal) = fractal {
+                            egui::Window::new("Fractal").show(context, |ui| {
+                                inspector::fractal_parameters(ui, fractal);
+                            });
+                        }
+                    },
+                );
+                renderer.set_gui(output);
+                renderer.set_ssao_parameters(ssao_parameters);
 
-            // Vulkan requires us to reverse the y axis for some reason
-            // Do this by setting up to -1
-            let view = camera.get_view_matrix();
+                if let Some((i, parameters)) = edited_material {
+                    phong_materials[i].1.set_parameters(parameters)?;
+                }
+            }
 
-            let should_print = false;
-            if should_print {
-                println!("view: ");
-                println!(
-                    "[{:10},{:10},{:10},{:10},",
-                    view[0], view[4], view[8], view[12]
-                );
-                println!(
-                    "{:10},{:10},{:10},{:10},",
-                    view[1], view[5], view[9], view[13]
-                );
-                println!(
-                    "{:10},{:10},{:10},{:10},",
-                    view[2], view[6], view[10], view[14]
-                );
-                println!(
-                    "{:10},{:10},{:10},{:10}]",
-                    view[3], view[7], view[11], view[15]
-                );
-                println!();
-                println!("projection: ");
-                println!(
-                    "[{:10},{:10},{:10},{:10},",
-                    projection[0], projection[4], projection[8], projection[12]
-                );
-                println!(
-                    "{:10},{:10},{:10},{:10},",
-                    projection[1], projection[5], projection[9], projection[13]
+            scene_graph.get_children_mut()[light_marker_index]
+                .set_parent_transform(light_marker_transform(&light.position), glm::identity());
+
+            // a click below selects through these views, so it is outlined from the next frame
+            scene_graph.set_highlighted(selected.as_slice());
+
+            let background = renderer.clear_color();
+            let fog_color = glm::vec3(background[0], background[1], background[2]);
+
+            let dimensions = renderer.render_dimensions();
+            let mut scene_views = Vec::with_capacity(camera_views.len());
+            for camera_view in &camera_views {
+                let camera = &camera_view.camera;
+                let (view_set, lighting_set) = view_sets(
+                    camera,
+                    camera_view.rect.aspect_ratio(dimensions),
+                    &light,
+                    &fog,
+                    fog_color,
+                    renderer.ambient_occlusion().occlusion(),
                 );
-                println!(
-                    "{:10},{:10},{:10},{:10},",
-                    projection[2], projection[6], projection[10], projection[14]
-                );
-                println!(
-                    "{:10},{:10},{:10},{:10}]",
-                    projection[3], projection[7], projection[11], projection[15]
-                );
-            }
 
-            let view_uniform_data = crate::material::phong::vs::ty::view_matrices {
-                view: view.into(),
-                projection: projection.into(),
-            };
+                scene_views.push(SceneView {
+                    rect: camera_view.rect,
+                    render_queue: RenderQueue::new(&scene_graph, camera.position()),
+                    view_set,
+                    lighting_set,
+                });
+            }
 
-            let sub_buffer_view_uniforms =
-                view_uniform_buffer_pool.next(view_uniform_data).unwrap();
+            let clicked = input_events.iter().any(|event| {
+                *event
+                    == InputEvent::MouseButton {
+                        button: MouseButton::Left,
+                        pressed: true,
+                    }
+            });
+            if input.focused && clicked && !gui.wants_pointer_input() && fractal.is_none() {
+                // while the cursor is grabbed, pick what the crosshair would be on
+                let position = if gui.visible() {
+                    [input.cursor_position.0, input.cursor_position.1]
+                } else {
+                    let size = self.surface.window().inner_size();
+                    [size.width as f64 / 2.0, size.height as f64 / 2.0]
+                };
+                renderer.pick(&scene_graph, &scene_views, position);
+            }
+            if let Some(picked) = renderer.picked() {
+                selected = picked;
+            }
 
-            let camera_layout = phong_material1.get_view_layout();
-            let camera_set = Arc::new(
-                PersistentDescriptorSet::start(camera_layout.clone())
-                    .add_buffer(sub_buffer_view_uniforms.clone())
-                    .unwrap()
-                    .build()
-                    .unwrap(),
+            let (view_set, lighting_set) = view_sets(
+                &security_camera,
+                1.0,
+                &light,
+                &fog,
+                fog_color,
+                renderer.ambient_occlusion().no_occlusion(),
             );
-
-            let lighting_uniform_data = crate::material::phong::fs::ty::light_parameters {
-                view_position: camera.position().into(),
-                light: crate::material::phong::fs::ty::Light {
-                    position: glm::vec3(0.0, 1.1, 0.0).into(),
-                    ambient: glm::vec3(0.2, 0.2, 0.2).into(),
-                    diffuse: glm::vec3(1.0, 1.0, 1.0).into(),
-                    specular: glm::vec3(1.0, 1.0, 1.0).into(),
-                    _dummy0: [0, 0, 0, 0],
-                    _dummy1: [0, 0, 0, 0],
-                    _dummy2: [0, 0, 0, 0],
+            let offscreen_views = [OffscreenView::new(
+                monitor_target.clone(),
+                SceneView {
+                    rect: ViewportRect::full(),
+                    render_queue: RenderQueue::new(&scene_graph, security_camera.position()),
+                    view_set,
+                    lighting_set,
                 },
-                _dummy0: [0, 0, 0, 0],
-            };
+            )];
 
-            let sub_buffer_lighting_uniforms = lighting_uniform_buffer_pool
-                .next(lighting_uniform_data)
-                .unwrap();
-
-            let lighting_layout = phong_material1.get_lighting_layout();
-            let lighting_set = Arc::new(
-                PersistentDescriptorSet::start(lighting_layout.clone())
-                    .add_buffer(sub_buffer_lighting_uniforms.clone())
-                    .unwrap()
-                    .build()
-                    .unwrap(),
+            let render_stats = renderer.stats();
+            stats_overlay.draw(
+                renderer.text(),
+                render_stats,
+                &camera_views[active_camera].camera,
             );
 
-            previous_frame_end =
-                renderer.render(&scene_graph, camera_set, lighting_set, previous_frame_end);
+            let debug = renderer.debug();
+            debug.grid(
+                glm::vec3(0.0, -1.0, 0.0),
+                10.0,
+                10,
+                glm::vec4(0.5, 0.5, 0.5, 0.5),
+            );
+            debug.frustum(
+                &security_camera,
+                1.0,
+                0.1,
+                1.0,
+                glm::vec4(1.0, 1.0, 0.0, 1.0),
+            );
+            debug.set_depth_test(false);
+            debug.axes(&glm::identity(), 1.0);
+            if let Some(node) = selected.and_then(|index| scene_graph.get_node(index)) {
+                debug.axes(&node.get_world_transform(), 0.5);
+            }
+            debug.set_depth_test(true);
+
+            renderer.set_fractal_explorer(fractal);
+            // the scene is hidden behind the fractal, so it isn't drawn at all
+            previous_frame_end = if fractal.is_some() {
+                renderer.render(&[], &[], previous_frame_end)
+            } else {
+                renderer.render(&offscreen_views, &scene_views, previous_frame_end)
+            };
             previous_frame_end
                 .as_mut()
                 .expect("Could not borrow future as mut")
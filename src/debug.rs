@@ -0,0 +1,290 @@
+use std::error;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use vulkano::buffer::CpuBufferPool;
+use vulkano::command_buffer::{AutoCommandBuffer, AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::DescriptorSet;
+use vulkano::device::{Device, Queue};
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::depth::DepthConfig;
+use crate::material::BlendMode;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/debug.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/debug.frag"
+    }
+}
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+vulkano::impl_vertex!(DebugVertex, position, color);
+
+/// Lines collected over a frame, drawn over the scene and then cleared
+pub struct DebugDraw {
+    depth_tested: Vec<DebugVertex>,
+    overlay: Vec<DebugVertex>,
+    depth_test: bool,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        DebugDraw {
+            depth_tested: vec![],
+            overlay: vec![],
+            depth_test: true,
+        }
+    }
+}
+
+impl DebugDraw {
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    /// Lines added afterwards are hidden behind the scene if `depth_test` is set,
+    /// otherwise they are drawn on top of it
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn line(&mut self, a: glm::Vec3, b: glm::Vec3, color: glm::Vec4) {
+        let lines = if self.depth_test {
+            &mut self.depth_tested
+        } else {
+            &mut self.overlay
+        };
+        lines.push(DebugVertex {
+            position: a.into(),
+            color: color.into(),
+        });
+        lines.push(DebugVertex {
+            position: b.into(),
+            color: color.into(),
+        });
+    }
+
+    /// The x, y, and z axes of `transform` in red, green, and blue
+    pub fn axes(&mut self, transform: &glm::Mat4, size: f32) {
+        let origin = transform * glm::vec4(0.0, 0.0, 0.0, 1.0);
+        let origin = origin.xyz() / origin.w;
+        let axes = [
+            (
+                glm::vec4(size, 0.0, 0.0, 0.0),
+                glm::vec4(1.0, 0.0, 0.0, 1.0),
+            ),
+            (
+                glm::vec4(0.0, size, 0.0, 0.0),
+                glm::vec4(0.0, 1.0, 0.0, 1.0),
+            ),
+            (
+                glm::vec4(0.0, 0.0, size, 0.0),
+                glm::vec4(0.0, 0.0, 1.0, 1.0),
+            ),
+        ];
+        for (axis, color) in axes.iter() {
+            let axis = (transform * axis).xyz();
+            self.line(origin, origin + axis, *color);
+        }
+    }
+
+    pub fn aabb(&mut self, min: glm::Vec3, max: glm::Vec3, color: glm::Vec4) {
+        let corner = |i: usize| {
+            glm::vec3(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        // corners whose indices differ by one bit share an edge
+        for i in 0..8 {
+            for bit in [1, 2, 4].iter() {
+                if i & bit == 0 {
+                    self.line(corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+
+    pub fn circle(
+        &mut self,
+        center: glm::Vec3,
+        axis_a: glm::Vec3,
+        axis_b: glm::Vec3,
+        radius: f32,
+        color: glm::Vec4,
+    ) {
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * PI;
+            center + (axis_a * angle.cos() + axis_b * angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    /// Drawn as a circle around each axis
+    pub fn sphere(&mut self, center: glm::Vec3, radius: f32, color: glm::Vec4) {
+        let x = glm::vec3(1.0, 0.0, 0.0);
+        let y = glm::vec3(0.0, 1.0, 0.0);
+        let z = glm::vec3(0.0, 0.0, 1.0);
+        self.circle(center, x, y, radius, color);
+        self.circle(center, y, z, radius, color);
+        self.circle(center, z, x, radius, color);
+    }
+
+    /// The volume `camera` sees between `near` and `far`
+    pub fn frustum(
+        &mut self,
+        camera: &Camera,
+        aspect_ratio: f32,
+        near: f32,
+        far: f32,
+        color: glm::Vec4,
+    ) {
+        let tan_half_fovy = (camera.zoom() / 2.0).tan();
+        let plane = |distance: f32| {
+            let center = camera.position() + camera.forward() * distance;
+            let up = camera.up() * tan_half_fovy * distance;
+            let right = camera.right() * tan_half_fovy * distance * aspect_ratio;
+            [
+                center - right - up,
+                center + right - up,
+                center + right + up,
+                center - right + up,
+            ]
+        };
+        let near = plane(near);
+        let far = plane(far);
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            self.line(near[i], near[next], color);
+            self.line(far[i], far[next], color);
+            self.line(near[i], far[i], color);
+        }
+    }
+
+    /// A grid on the xz plane, `size` units across with `divisions` cells per side, at least 1
+    pub fn grid(&mut self, center: glm::Vec3, size: f32, divisions: u32, color: glm::Vec4) {
+        let divisions = divisions.max(1);
+        let half = size / 2.0;
+        for i in 0..=divisions {
+            let offset = i as f32 / divisions as f32 * size - half;
+            self.line(
+                center + glm::vec3(offset, 0.0, -half),
+                center + glm::vec3(offset, 0.0, half),
+                color,
+            );
+            self.line(
+                center + glm::vec3(-half, 0.0, offset),
+                center + glm::vec3(half, 0.0, offset),
+                color,
+            );
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.overlay.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.depth_tested.clear();
+        self.overlay.clear();
+    }
+}
+
+/// Draws the lines of a `DebugDraw` with a line list pipeline
+pub struct DebugRenderer {
+    depth_tested_pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    overlay_pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    vertex_buffer_pool: CpuBufferPool<DebugVertex>,
+}
+
+impl DebugRenderer {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+        depth: DepthConfig,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let pipeline = |depth_stencil: DepthStencil| {
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<DebugVertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .line_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(depth_stencil)
+                .blend_collective(BlendMode::AlphaBlend.attachment_blend())
+                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+                .build(device.clone())
+        };
+
+        let depth_tested_pipeline = Arc::new(pipeline(depth.depth_stencil(false))?);
+        let overlay_pipeline = Arc::new(pipeline(DepthStencil::disabled())?);
+
+        Ok(DebugRenderer {
+            depth_tested_pipeline,
+            overlay_pipeline,
+            vertex_buffer_pool: CpuBufferPool::vertex_buffer(device),
+        })
+    }
+
+    pub fn draw(
+        &self,
+        queue: Arc<Queue>,
+        dynamic_state: &DynamicState,
+        view_set: Arc<dyn DescriptorSet + Send + Sync>,
+        debug: &DebugDraw,
+    ) -> Result<Vec<AutoCommandBuffer>, Box<dyn error::Error + Send + Sync>> {
+        let batches = [
+            (self.depth_tested_pipeline.clone(), &debug.depth_tested),
+            (self.overlay_pipeline.clone(), &debug.overlay),
+        ];
+
+        let mut command_buffers = vec![];
+        for (pipeline, vertices) in batches.iter() {
+            if vertices.is_empty() {
+                continue;
+            }
+            let vertex_buffer = self.vertex_buffer_pool.chunk(vertices.iter().cloned())?;
+
+            let mut builder = AutoCommandBufferBuilder::secondary_graphics(
+                pipeline.device().clone(),
+                queue.family(),
+                pipeline.clone().subpass(),
+            )?;
+            builder.draw(
+                pipeline.clone(),
+                dynamic_state,
+                vec![Arc::new(vertex_buffer)],
+                view_set.clone(),
+                (),
+            )?;
+            command_buffers.push(builder.build()?);
+        }
+        Ok(command_buffers)
+    }
+}
@@ -0,0 +1,56 @@
+use vulkano::pipeline::depth_stencil::{Compare, DepthStencil};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthConfig {
+    /// Maps the near plane to a depth of 1 and the far plane to 0, which keeps
+    /// floating point precision roughly even over distance
+    pub reverse_z: bool,
+    pub near: f32,
+    /// `None` puts the far plane at infinity
+    pub far: Option<f32>,
+}
+
+impl Default for DepthConfig {
+    fn default() -> Self {
+        DepthConfig {
+            reverse_z: false,
+            near: 0.1,
+            far: Some(100.0),
+        }
+    }
+}
+
+impl DepthConfig {
+    pub fn reverse_z_infinite(near: f32) -> Self {
+        DepthConfig {
+            reverse_z: true,
+            near,
+            far: None,
+        }
+    }
+
+    /// The value the depth buffer is cleared to, i.e. the depth of the far plane
+    pub fn clear_value(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn compare(&self) -> Compare {
+        if self.reverse_z {
+            Compare::Greater
+        } else {
+            Compare::Less
+        }
+    }
+
+    pub fn depth_stencil(&self, depth_write: bool) -> DepthStencil {
+        DepthStencil {
+            depth_compare: self.compare(),
+            depth_write,
+            ..DepthStencil::simple_depth_test()
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/// How fog thickens with distance from the camera
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FogMode {
+    #[default]
+    None,
+    /// No fog closer than `start`, fully fogged beyond `end`
+    Linear {
+        start: f32,
+        end: f32,
+    },
+    Exponential {
+        density: f32,
+    },
+    /// Stays clear for longer than `Exponential`, then thickens faster
+    ExponentialSquared {
+        density: f32,
+    },
+}
+
+/// Fog lying low, thinning out exponentially above `base_height`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeightFog {
+    /// Density at `base_height`
+    pub density: f32,
+    /// How quickly the density drops with height
+    pub falloff: f32,
+    pub base_height: f32,
+}
+
+impl Default for HeightFog {
+    fn default() -> Self {
+        HeightFog {
+            density: 0.1,
+            falloff: 1.0,
+            base_height: 0.0,
+        }
+    }
+}
+
+/// Distance and height fog applied by every lit material, through `uniforms::LightingUniforms`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub height: Option<HeightFog>,
+}
@@ -0,0 +1,187 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::CpuBufferPool;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Device;
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::GraphicsPipeline;
+
+use crate::material::fractal::{FractalKind, FractalParameters};
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/fullscreen.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/fractal_explorer.frag"
+    }
+}
+
+pub mod fs64 {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/fractal_explorer.frag",
+        define: [("FRACTAL_DOUBLE", "1")]
+    }
+}
+
+type ExplorerPipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+enum Precision {
+    Single(CpuBufferPool<fs::ty::explorer_parameters>),
+    Double(CpuBufferPool<fs64::ty::explorer_parameters>),
+}
+
+/// Draws a fractal over the whole of the current viewport. Single precision gives out at a
+/// zoom of around 10^4, double precision, used when the device has `shader_f3264` enabled,
+/// at around 10^13.
+pub struct FractalExplorer {
+    pipeline: Arc<ExplorerPipeline>,
+    precision: Precision,
+}
+
+impl FractalExplorer {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let subpass = Subpass::from(render_pass, 0).unwrap();
+
+        let explorer = if device.enabled_features().shader_f3264 {
+            let fs = fs64::Shader::load(device.clone()).expect("failed to create shader module");
+            FractalExplorer {
+                pipeline: Arc::new(
+                    GraphicsPipeline::start()
+                        .vertex_input(BufferlessDefinition)
+                        .vertex_shader(vs.main_entry_point(), ())
+                        .triangle_list()
+                        .viewports_dynamic_scissors_irrelevant(1)
+                        .fragment_shader(fs.main_entry_point(), ())
+                        .render_pass(subpass)
+                        .build(device.clone())?,
+                ),
+                precision: Precision::Double(CpuBufferPool::uniform_buffer(device)),
+            }
+        } else {
+            let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+            FractalExplorer {
+                pipeline: Arc::new(
+                    GraphicsPipeline::start()
+                        .vertex_input(BufferlessDefinition)
+                        .vertex_shader(vs.main_entry_point(), ())
+                        .triangle_list()
+                        .viewports_dynamic_scissors_irrelevant(1)
+                        .fragment_shader(fs.main_entry_point(), ())
+                        .render_pass(subpass)
+                        .build(device.clone())?,
+                ),
+                precision: Precision::Single(CpuBufferPool::uniform_buffer(device)),
+            }
+        };
+        Ok(explorer)
+    }
+
+    pub fn is_double_precision(&self) -> bool {
+        match self.precision {
+            Precision::Single(_) => false,
+            Precision::Double(_) => true,
+        }
+    }
+
+    /// `dimensions` are the viewport's, in pixels
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        parameters: &FractalParameters,
+        dimensions: [u32; 2],
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let extent = parameters.extent(dimensions);
+        let center = parameters.center;
+        let (kind, julia_c) = match parameters.kind {
+            FractalKind::Mandelbrot => (0, [0.0, 0.0]),
+            FractalKind::Julia { c } => (1, [c.x, c.y]),
+        };
+        let palette = parameters.palette;
+
+        let layout = self.pipeline.descriptor_set_layout(0).unwrap();
+        let set: Arc<dyn DescriptorSet + Send + Sync> = match self.precision {
+            Precision::Single(ref pool) => {
+                let uniforms = fs::ty::explorer_parameters {
+                    fractal: fs::ty::Fractal {
+                        center: [center.x as f32, center.y as f32],
+                        extent: [extent.x as f32, extent.y as f32],
+                        julia_c: [julia_c[0] as f32, julia_c[1] as f32],
+                        kind,
+                        max_iterations: parameters.max_iterations as i32,
+                        escape_radius: parameters.escape_radius,
+                        palette_offset: palette.offset.into(),
+                        palette_amplitude: palette.amplitude.into(),
+                        palette_frequency: palette.frequency.into(),
+                        palette_phase: palette.phase.into(),
+                        _dummy0: [0; 12],
+                        _dummy1: [0, 0, 0, 0],
+                        _dummy2: [0, 0, 0, 0],
+                        _dummy3: [0, 0, 0, 0],
+                    },
+                };
+                Arc::new(
+                    PersistentDescriptorSet::start(layout.clone())
+                        .add_buffer(pool.next(uniforms)?)?
+                        .build()?,
+                )
+            }
+            Precision::Double(ref pool) => {
+                let uniforms = fs64::ty::explorer_parameters {
+                    fractal: fs64::ty::Fractal {
+                        center: center.into(),
+                        extent: extent.into(),
+                        julia_c,
+                        kind,
+                        max_iterations: parameters.max_iterations as i32,
+                        escape_radius: parameters.escape_radius,
+                        palette_offset: palette.offset.into(),
+                        palette_amplitude: palette.amplitude.into(),
+                        palette_frequency: palette.frequency.into(),
+                        palette_phase: palette.phase.into(),
+                        _dummy0: [0, 0, 0, 0],
+                        _dummy1: [0, 0, 0, 0],
+                        _dummy2: [0, 0, 0, 0],
+                        _dummy3: [0, 0, 0, 0],
+                    },
+                };
+                Arc::new(
+                    PersistentDescriptorSet::start(layout.clone())
+                        .add_buffer(pool.next(uniforms)?)?
+                        .build()?,
+                )
+            }
+        };
+
+        builder.draw(
+            self.pipeline.clone(),
+            dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            set,
+            (),
+        )?;
+        Ok(())
+    }
+}
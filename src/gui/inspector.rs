@@ -0,0 +1,326 @@
+use nalgebra_glm as glm;
+
+use crate::fog::{Fog, FogMode, HeightFog};
+use crate::light::PointLight;
+use crate::material::fractal::{FractalKind, FractalParameters};
+use crate::material::phong::PhongParameters;
+use crate::scene::SceneGraph;
+use crate::ssao::SsaoParameters;
+
+/// Splits a transform built by `compose` back into its translation, rotation in degrees, and scale
+fn decompose(transform: &glm::Mat4) -> (glm::Vec3, glm::Vec3, glm::Vec3) {
+    let column = |i: usize| glm::vec3(transform[(0, i)], transform[(1, i)], transform[(2, i)]);
+    let translation = column(3);
+    let scale = glm::vec3(column(0).norm(), column(1).norm(), column(2).norm());
+    let rotation =
+        |row: usize, column: usize| transform[(row, column)] / scale[column].max(f32::EPSILON);
+
+    let x = rotation(2, 1).atan2(rotation(2, 2));
+    let y = (-rotation(2, 0)).clamp(-1.0, 1.0).asin();
+    let z = rotation(1, 0).atan2(rotation(0, 0));
+    (translation, glm::degrees(&glm::vec3(x, y, z)), scale)
+}
+
+/// Translation, then rotation around z, y, and x, then scale
+fn compose(translation: &glm::Vec3, rotation: &glm::Vec3, scale: &glm::Vec3) -> glm::Mat4 {
+    let rotation = glm::radians(rotation);
+    let transform = glm::translate(&glm::identity(), translation);
+    let transform = glm::rotate_z(&transform, rotation.z);
+    let transform = glm::rotate_y(&transform, rotation.y);
+    let transform = glm::rotate_x(&transform, rotation.x);
+    glm::scale(&transform, scale)
+}
+
+fn vector(ui: &mut egui::Ui, vector: &mut glm::Vec3, speed: f32) -> bool {
+    let mut changed = false;
+    for i in 0..3 {
+        changed |= ui
+            .add(egui::DragValue::new(&mut vector[i]).speed(speed))
+            .changed();
+    }
+    changed
+}
+
+fn color(ui: &mut egui::Ui, color: &mut glm::Vec3) -> bool {
+    let mut rgb: [f32; 3] = (*color).into();
+    let changed = ui.color_edit_button_rgb(&mut rgb).changed();
+    *color = rgb.into();
+    changed
+}
+
+fn transform(ui: &mut egui::Ui, id: &str, node: &mut SceneGraph, parents_transform: glm::Mat4) {
+    let (mut translation, mut rotation, mut scale) = decompose(&node.get_parent_transform());
+    let mut changed = false;
+    egui::Grid::new(id).show(ui, |ui| {
+        ui.label("Translation");
+        changed |= vector(ui, &mut translation, 0.05);
+        ui.end_row();
+        ui.label("Rotation");
+        changed |= vector(ui, &mut rotation, 1.0);
+        ui.end_row();
+        ui.label("Scale");
+        changed |= vector(ui, &mut scale, 0.01);
+        ui.end_row();
+    });
+    // only rebuilt when edited, so transforms that don't decompose exactly are left alone
+    if changed {
+        node.set_parent_transform(compose(&translation, &rotation, &scale), parents_transform);
+    }
+}
+
+/// `index` counts nodes like `SceneGraph::visit`
+fn node(
+    ui: &mut egui::Ui,
+    id: String,
+    index: &mut usize,
+    selected: Option<usize>,
+    node: &mut SceneGraph,
+    parents_transform: glm::Mat4,
+) {
+    let first = *index;
+    let mut size = 0;
+    node.visit(&mut |_, _| size += 1);
+    let is_selected = selected == Some(first);
+    *index += 1;
+
+    let mut label = if node.get_name().is_empty() {
+        "Node".to_string()
+    } else {
+        node.get_name().to_string()
+    };
+    if node.get_object().is_some() {
+        label.push_str(" (object)");
+    }
+    if node.get_emitter().is_some() {
+        label.push_str(" (emitter)");
+    }
+
+    let mut label = egui::RichText::new(label);
+    if is_selected {
+        label = label.strong().color(egui::Color32::YELLOW);
+    }
+
+    egui::CollapsingHeader::new(label)
+        .id_salt(&id)
+        .default_open(true)
+        .show(ui, |ui| {
+            transform(ui, &id, node, parents_transform);
+            let world_transform = node.get_world_transform();
+            for (i, child) in node.get_children_mut().iter_mut().enumerate() {
+                self::node(
+                    ui,
+                    format!("{}/{}", id, i),
+                    index,
+                    selected,
+                    child,
+                    world_transform,
+                );
+            }
+        });
+    // children of a collapsed node aren't visited, but still count
+    *index = first + size;
+}
+
+/// The graph as a tree, with each node's parent transform editable and
+/// the node `SceneGraph::visit` gives `selected` highlighted
+pub fn scene_graph(ui: &mut egui::Ui, scene_graph: &mut SceneGraph, selected: Option<usize>) {
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        node(
+            ui,
+            "scene_graph".to_string(),
+            &mut 0,
+            selected,
+            scene_graph,
+            glm::identity(),
+        );
+    });
+}
+
+/// Returns whether anything changed, to be passed on with `Phong::set_parameters`
+pub fn phong_parameters(ui: &mut egui::Ui, id: &str, parameters: &mut PhongParameters) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id).show(ui, |ui| {
+        ui.label("Ambient");
+        changed |= color(ui, &mut parameters.ambient);
+        ui.end_row();
+        ui.label("Diffuse");
+        changed |= color(ui, &mut parameters.diffuse);
+        ui.end_row();
+        ui.label("Specular");
+        changed |= color(ui, &mut parameters.specular);
+        ui.end_row();
+        ui.label("Shininess");
+        changed |= ui
+            .add(egui::DragValue::new(&mut parameters.shininess).range(1.0..=256.0))
+            .changed();
+        ui.end_row();
+        ui.label("Alpha");
+        changed |= ui
+            .add(egui::Slider::new(&mut parameters.alpha, 0.0..=1.0))
+            .changed();
+        ui.end_row();
+    });
+    changed
+}
+
+pub fn point_light(ui: &mut egui::Ui, light: &mut PointLight) {
+    egui::Grid::new("point_light").show(ui, |ui| {
+        ui.label("Position");
+        vector(ui, &mut light.position, 0.05);
+        ui.end_row();
+        ui.label("Ambient");
+        color(ui, &mut light.ambient);
+        ui.end_row();
+        ui.label("Diffuse");
+        color(ui, &mut light.diffuse);
+        ui.end_row();
+        ui.label("Specular");
+        color(ui, &mut light.specular);
+        ui.end_row();
+    });
+}
+
+pub fn ssao_parameters(ui: &mut egui::Ui, parameters: &mut SsaoParameters) {
+    egui::Grid::new("ssao_parameters").show(ui, |ui| {
+        ui.label("Radius");
+        ui.add(egui::Slider::new(&mut parameters.radius, 0.05..=2.0));
+        ui.end_row();
+        ui.label("Intensity");
+        ui.add(egui::Slider::new(&mut parameters.intensity, 0.0..=2.0));
+        ui.end_row();
+        ui.label("Samples");
+        ui.add(egui::Slider::new(&mut parameters.sample_count, 1..=64));
+        ui.end_row();
+    });
+}
+
+pub fn fog(ui: &mut egui::Ui, fog: &mut Fog) {
+    egui::Grid::new("fog").show(ui, |ui| {
+        let modes = [
+            ("None", FogMode::None),
+            (
+                "Linear",
+                FogMode::Linear {
+                    start: 5.0,
+                    end: 30.0,
+                },
+            ),
+            ("Exponential", FogMode::Exponential { density: 0.05 }),
+            (
+                "Exponential squared",
+                FogMode::ExponentialSquared { density: 0.05 },
+            ),
+        ];
+        let selected = modes
+            .iter()
+            .find(|(_, mode)| std::mem::discriminant(mode) == std::mem::discriminant(&fog.mode))
+            .map_or("None", |(name, _)| *name);
+        ui.label("Mode");
+        egui::ComboBox::from_id_salt("fog_mode")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (name, mode) in modes {
+                    if ui.selectable_label(selected == name, name).clicked() && selected != name {
+                        fog.mode = mode;
+                    }
+                }
+            });
+        ui.end_row();
+
+        match fog.mode {
+            FogMode::None => {}
+            FogMode::Linear {
+                ref mut start,
+                ref mut end,
+            } => {
+                ui.label("Start");
+                ui.add(egui::DragValue::new(start).speed(0.1));
+                ui.end_row();
+                ui.label("End");
+                ui.add(egui::DragValue::new(end).speed(0.1));
+                ui.end_row();
+            }
+            FogMode::Exponential { ref mut density }
+            | FogMode::ExponentialSquared { ref mut density } => {
+                ui.label("Density");
+                ui.add(egui::Slider::new(density, 0.0..=0.5));
+                ui.end_row();
+            }
+        }
+
+        let mut height_fog = fog.height.is_some();
+        ui.label("Height fog");
+        ui.checkbox(&mut height_fog, "");
+        ui.end_row();
+        if height_fog != fog.height.is_some() {
+            fog.height = if height_fog {
+                Some(HeightFog::default())
+            } else {
+                None
+            };
+        }
+        if let Some(ref mut height) = fog.height {
+            ui.label("Density");
+            ui.add(egui::Slider::new(&mut height.density, 0.0..=1.0));
+            ui.end_row();
+            ui.label("Falloff");
+            ui.add(egui::Slider::new(&mut height.falloff, 0.01..=5.0));
+            ui.end_row();
+            ui.label("Base height");
+            ui.add(egui::DragValue::new(&mut height.base_height).speed(0.05));
+            ui.end_row();
+        }
+    });
+}
+
+/// The view itself is moved with the mouse, so the center and zoom are only shown
+pub fn fractal_parameters(ui: &mut egui::Ui, parameters: &mut FractalParameters) {
+    egui::Grid::new("fractal_parameters").show(ui, |ui| {
+        let mut julia = matches!(parameters.kind, FractalKind::Julia { .. });
+        ui.label("Julia set");
+        if ui.checkbox(&mut julia, "").changed() {
+            parameters.kind = if julia {
+                FractalKind::Julia {
+                    c: glm::vec2(-0.8, 0.156),
+                }
+            } else {
+                FractalKind::Mandelbrot
+            };
+        }
+        ui.end_row();
+        if let FractalKind::Julia { ref mut c } = parameters.kind {
+            ui.label("C");
+            ui.add(egui::DragValue::new(&mut c.x).speed(0.001));
+            ui.add(egui::DragValue::new(&mut c.y).speed(0.001));
+            ui.end_row();
+        }
+        ui.label("Center");
+        ui.label(format!(
+            "{:.15}, {:.15}",
+            parameters.center.x, parameters.center.y
+        ));
+        ui.end_row();
+        ui.label("Zoom");
+        ui.label(format!("{:.3e}", parameters.zoom));
+        ui.end_row();
+        ui.label("Iterations");
+        ui.add(egui::Slider::new(&mut parameters.max_iterations, 16..=4096).logarithmic(true));
+        ui.end_row();
+        ui.label("Escape radius");
+        ui.add(egui::Slider::new(&mut parameters.escape_radius, 2.0..=256.0).logarithmic(true));
+        ui.end_row();
+        let palette = &mut parameters.palette;
+        ui.label("Palette offset");
+        vector(ui, &mut palette.offset, 0.01);
+        ui.end_row();
+        ui.label("Palette amplitude");
+        vector(ui, &mut palette.amplitude, 0.01);
+        ui.end_row();
+        ui.label("Palette frequency");
+        vector(ui, &mut palette.frequency, 0.05);
+        ui.end_row();
+        ui.label("Palette phase");
+        vector(ui, &mut palette.phase, 0.01);
+        ui.end_row();
+    });
+}
@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+use std::error;
+use std::sync::Arc;
+use std::time::Instant;
+
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer, CpuBufferPool};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::image::{Dimensions, ImageUsage, StorageImage};
+use vulkano::pipeline::viewport::Scissor;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+use winit::event::{ModifiersState, MouseButton, VirtualKeyCode};
+
+use crate::input::{Input, InputEvent};
+use crate::material::BlendMode;
+
+pub mod inspector;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/gui.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/gui.frag"
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct GuiVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+vulkano::impl_vertex!(GuiVertex, position, uv, color);
+
+fn egui_modifiers(modifiers: ModifiersState) -> egui::Modifiers {
+    egui::Modifiers {
+        alt: modifiers.alt(),
+        ctrl: modifiers.ctrl(),
+        shift: modifiers.shift(),
+        mac_cmd: false,
+        command: modifiers.ctrl(),
+    }
+}
+
+/// Only the keys used to edit text and move between widgets
+fn egui_key(key: VirtualKeyCode) -> Option<egui::Key> {
+    Some(match key {
+        VirtualKeyCode::Back => egui::Key::Backspace,
+        VirtualKeyCode::Delete => egui::Key::Delete,
+        VirtualKeyCode::Return => egui::Key::Enter,
+        VirtualKeyCode::Tab => egui::Key::Tab,
+        VirtualKeyCode::Escape => egui::Key::Escape,
+        VirtualKeyCode::Left => egui::Key::ArrowLeft,
+        VirtualKeyCode::Right => egui::Key::ArrowRight,
+        VirtualKeyCode::Up => egui::Key::ArrowUp,
+        VirtualKeyCode::Down => egui::Key::ArrowDown,
+        VirtualKeyCode::Home => egui::Key::Home,
+        VirtualKeyCode::End => egui::Key::End,
+        VirtualKeyCode::A => egui::Key::A,
+        VirtualKeyCode::Z => egui::Key::Z,
+        _ => return None,
+    })
+}
+
+fn egui_pointer_button(button: MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        MouseButton::Left => Some(egui::PointerButton::Primary),
+        MouseButton::Right => Some(egui::PointerButton::Secondary),
+        MouseButton::Middle => Some(egui::PointerButton::Middle),
+        MouseButton::Other(_) => None,
+    }
+}
+
+/// A frame of the GUI, tessellated and ready for the `GuiRenderer`
+pub struct GuiOutput {
+    primitives: Vec<egui::ClippedPrimitive>,
+    textures_delta: egui::TexturesDelta,
+    pixels_per_point: f32,
+}
+
+/// An egui context fed from `Input`, hidden until toggled
+pub struct Gui {
+    context: egui::Context,
+    start_time: Instant,
+    cursor_position: egui::Pos2,
+    visible: bool,
+}
+
+impl Default for Gui {
+    fn default() -> Self {
+        Gui {
+            context: egui::Context::default(),
+            start_time: Instant::now(),
+            cursor_position: egui::Pos2::ZERO,
+            visible: false,
+        }
+    }
+}
+
+impl Gui {
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn context(&self) -> &egui::Context {
+        &self.context
+    }
+
+    /// Set while the pointer is over a window or dragging a widget, clicks then shouldn't reach the scene
+    pub fn wants_pointer_input(&self) -> bool {
+        self.visible && self.context.wants_pointer_input()
+    }
+
+    /// Set while a text field has focus, keys then shouldn't move the camera
+    pub fn wants_keyboard_input(&self) -> bool {
+        self.visible && self.context.wants_keyboard_input()
+    }
+
+    /// Runs `ui` with the input since the last frame, `screen_size` is in window pixels
+    pub fn run(
+        &mut self,
+        input: &Input,
+        events: &[InputEvent],
+        screen_size: [u32; 2],
+        pixels_per_point: f32,
+        ui: impl FnMut(&egui::Context),
+    ) -> GuiOutput {
+        let modifiers = egui_modifiers(input.modifiers);
+        let cursor_position = egui::pos2(
+            input.cursor_position.0 as f32 / pixels_per_point,
+            input.cursor_position.1 as f32 / pixels_per_point,
+        );
+
+        let mut raw_events = vec![];
+        if cursor_position != self.cursor_position {
+            raw_events.push(egui::Event::PointerMoved(cursor_position));
+            self.cursor_position = cursor_position;
+        }
+        for event in events {
+            match *event {
+                InputEvent::Character(c) if !c.is_control() => {
+                    raw_events.push(egui::Event::Text(c.to_string()))
+                }
+                InputEvent::Character(_) => (),
+                InputEvent::Key { key, pressed } => {
+                    if let Some(key) = egui_key(key) {
+                        raw_events.push(egui::Event::Key {
+                            key,
+                            physical_key: None,
+                            pressed,
+                            repeat: false,
+                            modifiers,
+                        });
+                    }
+                }
+                InputEvent::MouseButton { button, pressed } => {
+                    if let Some(button) = egui_pointer_button(button) {
+                        raw_events.push(egui::Event::PointerButton {
+                            pos: cursor_position,
+                            button,
+                            pressed,
+                            modifiers,
+                        });
+                    }
+                }
+            }
+        }
+        if input.mouse_wheel_delta != 0.0 {
+            raw_events.push(egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Line,
+                delta: egui::vec2(0.0, input.mouse_wheel_delta as f32),
+                modifiers,
+            });
+        }
+
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(screen_size[0] as f32, screen_size[1] as f32) / pixels_per_point,
+            )),
+            time: Some(self.start_time.elapsed().as_secs_f64()),
+            modifiers,
+            events: raw_events,
+            focused: input.focused,
+            ..egui::RawInput::default()
+        };
+
+        self.context.set_pixels_per_point(pixels_per_point);
+        let output = self.context.run(raw_input, ui);
+        GuiOutput {
+            primitives: self
+                .context
+                .tessellate(output.shapes, output.pixels_per_point),
+            textures_delta: output.textures_delta,
+            pixels_per_point: output.pixels_per_point,
+        }
+    }
+}
+
+type GuiTexture = (
+    Arc<StorageImage<Format>>,
+    Arc<dyn DescriptorSet + Send + Sync>,
+);
+
+/// Draws a `GuiOutput` over the final image, keeping egui's textures on the GPU
+pub struct GuiRenderer {
+    queue: Arc<Queue>,
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    sampler: Arc<Sampler>,
+    textures: HashMap<egui::TextureId, GuiTexture>,
+    textures_delta: egui::TexturesDelta,
+    primitives: Vec<egui::ClippedPrimitive>,
+    pixels_per_point: f32,
+    vertex_buffer_pool: CpuBufferPool<GuiVertex>,
+    index_buffer_pool: CpuBufferPool<u32>,
+}
+
+impl GuiRenderer {
+    pub fn new(
+        queue: Arc<Queue>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<GuiVertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_scissors_dynamic(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .blend_collective(BlendMode::Premultiplied.attachment_blend())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let sampler = Sampler::new(
+            device.clone(),
+            Filter::Linear,
+            Filter::Linear,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        Ok(GuiRenderer {
+            queue,
+            pipeline,
+            sampler,
+            textures: HashMap::new(),
+            textures_delta: egui::TexturesDelta::default(),
+            primitives: vec![],
+            pixels_per_point: 1.0,
+            vertex_buffer_pool: CpuBufferPool::vertex_buffer(device.clone()),
+            index_buffer_pool: CpuBufferPool::new(device, BufferUsage::index_buffer()),
+        })
+    }
+
+    /// Replaces what is drawn in the next frame, texture changes are kept until
+    /// they are uploaded so none are lost if a frame is skipped
+    pub fn set_output(&mut self, output: GuiOutput) {
+        self.textures_delta.append(output.textures_delta);
+        self.primitives = output.primitives;
+        self.pixels_per_point = output.pixels_per_point;
+    }
+
+    /// Records the texture changes, must be outside of a render pass
+    pub fn upload(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let device = self.queue.device().clone();
+        for (id, delta) in std::mem::take(&mut self.textures_delta.set) {
+            let [width, height] = delta.image.size();
+            let pixels: Vec<[u8; 4]> = match delta.image {
+                egui::ImageData::Color(image) => {
+                    image.pixels.iter().map(|pixel| pixel.to_array()).collect()
+                }
+                egui::ImageData::Font(image) => image
+                    .srgba_pixels(None)
+                    .map(|pixel| pixel.to_array())
+                    .collect(),
+            };
+
+            let image = match delta.pos {
+                Some(_) => match self.textures.get(&id) {
+                    Some((image, _)) => image.clone(),
+                    None => continue,
+                },
+                None => {
+                    let image = StorageImage::with_usage(
+                        device.clone(),
+                        Dimensions::Dim2d {
+                            width: width as u32,
+                            height: height as u32,
+                        },
+                        Format::R8G8B8A8Srgb,
+                        ImageUsage {
+                            transfer_destination: true,
+                            sampled: true,
+                            ..ImageUsage::none()
+                        },
+                        vec![self.queue.family()],
+                    )?;
+                    let layout = self.pipeline.descriptor_set_layout(0).unwrap();
+                    let set = Arc::new(
+                        PersistentDescriptorSet::start(layout.clone())
+                            .add_sampled_image(image.clone(), self.sampler.clone())?
+                            .build()?,
+                    );
+                    self.textures.insert(id, (image.clone(), set));
+                    image
+                }
+            };
+
+            let [x, y] = delta.pos.unwrap_or([0, 0]);
+            let buffer = CpuAccessibleBuffer::from_iter(
+                device.clone(),
+                BufferUsage::transfer_source(),
+                false,
+                pixels.into_iter(),
+            )?;
+            builder.copy_buffer_to_image_dimensions(
+                buffer,
+                image,
+                [x as u32, y as u32, 0],
+                [width as u32, height as u32, 1],
+                0,
+                1,
+                0,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the output set since the last frame, then frees the textures egui is done with
+    pub fn draw(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        screen_size: [u32; 2],
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let pixels_per_point = self.pixels_per_point;
+        let [width, height] = [screen_size[0] as f32, screen_size[1] as f32];
+
+        for primitive in self.primitives.drain(..) {
+            let mesh = match primitive.primitive {
+                egui::epaint::Primitive::Mesh(mesh) => mesh,
+                egui::epaint::Primitive::Callback(_) => continue,
+            };
+            let set = match self.textures.get(&mesh.texture_id) {
+                Some((_, set)) => set.clone(),
+                None => continue,
+            };
+
+            // the clip rectangle is in points, scissors are in pixels inside the screen
+            let clip_rect = primitive.clip_rect;
+            let min_x = (clip_rect.min.x * pixels_per_point)
+                .round()
+                .clamp(0.0, width);
+            let min_y = (clip_rect.min.y * pixels_per_point)
+                .round()
+                .clamp(0.0, height);
+            let max_x = (clip_rect.max.x * pixels_per_point)
+                .round()
+                .clamp(0.0, width);
+            let max_y = (clip_rect.max.y * pixels_per_point)
+                .round()
+                .clamp(0.0, height);
+            if mesh.indices.is_empty() || max_x <= min_x || max_y <= min_y {
+                continue;
+            }
+            let dynamic_state = DynamicState {
+                scissors: Some(vec![Scissor {
+                    origin: [min_x as i32, min_y as i32],
+                    dimensions: [(max_x - min_x) as u32, (max_y - min_y) as u32],
+                }]),
+                ..dynamic_state.clone()
+            };
+
+            let vertex_buffer =
+                self.vertex_buffer_pool
+                    .chunk(mesh.vertices.iter().map(|vertex| GuiVertex {
+                        position: [vertex.pos.x, vertex.pos.y],
+                        uv: [vertex.uv.x, vertex.uv.y],
+                        color: vertex.color.to_array().map(|c| c as f32 / 255.0),
+                    }))?;
+            let index_buffer = self.index_buffer_pool.chunk(mesh.indices)?;
+            builder.draw_indexed(
+                self.pipeline.clone(),
+                &dynamic_state,
+                vec![Arc::new(vertex_buffer)],
+                index_buffer,
+                set,
+                vs::ty::gui_parameters {
+                    screen_size: [width / pixels_per_point, height / pixels_per_point],
+                },
+            )?;
+        }
+
+        for id in std::mem::take(&mut self.textures_delta.free) {
+            self.textures.remove(&id);
+        }
+        Ok(())
+    }
+}
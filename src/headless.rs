@@ -0,0 +1,156 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBuffer};
+use vulkano::format::Format;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::sync::GpuFuture;
+
+use crate::context::RenderContext;
+use crate::depth::DepthConfig;
+use crate::material::pipelines::MaterialPipelines;
+use crate::render_target::{scene_render_pass, RenderTarget};
+use crate::renderer::{dispatch_emitters, draw_views, SceneView, CLEAR_COLOR};
+use crate::ssao::AmbientOcclusion;
+use crate::uniforms::SceneLayouts;
+use crate::view::ViewportClear;
+
+/// Read back as sRGB, so the pixels match what a window would show
+const COLOR_FORMAT: Format = Format::R8G8B8A8Srgb;
+
+/// Renders scene views into a fixed size target and reads the pixels back, without a window
+pub struct HeadlessRenderer {
+    context: Arc<RenderContext>,
+    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    target: RenderTarget,
+    viewport_clear: ViewportClear,
+    ambient_occlusion: AmbientOcclusion,
+    scene_layouts: Arc<SceneLayouts>,
+    material_pipelines: Arc<MaterialPipelines>,
+    depth_config: DepthConfig,
+    clear_color: [f32; 4],
+}
+
+impl HeadlessRenderer {
+    pub fn new(
+        context: Arc<RenderContext>,
+        dimensions: [u32; 2],
+        depth_config: DepthConfig,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let render_pass = scene_render_pass(context.device(), COLOR_FORMAT)?;
+        let target = RenderTarget::new(
+            context.device(),
+            render_pass.clone(),
+            dimensions,
+            COLOR_FORMAT,
+        )?;
+        let viewport_clear = ViewportClear::new(context.device(), render_pass.clone())?;
+        let ambient_occlusion = AmbientOcclusion::new(context.queue(), dimensions, depth_config)?;
+        let scene_layouts = Arc::new(SceneLayouts::new(context.device())?);
+        let material_pipelines =
+            MaterialPipelines::new(context.device(), render_pass.clone(), depth_config);
+
+        Ok(HeadlessRenderer {
+            context,
+            render_pass,
+            target,
+            viewport_clear,
+            ambient_occlusion,
+            scene_layouts,
+            material_pipelines,
+            depth_config,
+            clear_color: CLEAR_COLOR,
+        })
+    }
+
+    /// The render pass materials drawn by this renderer must build their pipelines against
+    pub fn render_pass(&self) -> Arc<dyn RenderPassAbstract + Send + Sync> {
+        self.render_pass.clone()
+    }
+
+    pub fn depth_config(&self) -> DepthConfig {
+        self.depth_config
+    }
+
+    /// Where materials drawn by this renderer get their pipelines
+    pub fn material_pipelines(&self) -> Arc<MaterialPipelines> {
+        self.material_pipelines.clone()
+    }
+
+    pub fn clear_color(&self) -> [f32; 4] {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: [f32; 4]) {
+        self.clear_color = clear_color;
+    }
+
+    /// The layouts the view and lighting sets of every `SceneView` are built against
+    pub fn scene_layouts(&self) -> Arc<SceneLayouts> {
+        self.scene_layouts.clone()
+    }
+
+    /// Lit materials must sample `AmbientOcclusion::occlusion` in their lighting sets
+    pub fn ambient_occlusion(&self) -> &AmbientOcclusion {
+        &self.ambient_occlusion
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.target.dimensions()
+    }
+
+    /// Draws `views` like the main views of a frame and waits for the result,
+    /// returned as RGBA rows from the top left
+    pub fn render(
+        &self,
+        views: &[SceneView],
+    ) -> Result<Vec<u8>, Box<dyn error::Error + Send + Sync>> {
+        let device = self.context.device();
+        let queue = self.context.queue();
+        let [width, height] = self.target.dimensions();
+
+        let pixels = CpuAccessibleBuffer::from_iter(
+            device.clone(),
+            BufferUsage::transfer_destination(),
+            false,
+            (0..width * height * 4).map(|_| 0u8),
+        )?;
+
+        let mut builder =
+            AutoCommandBufferBuilder::primary_one_time_submit(device, queue.family())?;
+        dispatch_emitters(&mut builder, views.iter());
+        let views: Vec<&SceneView> = views.iter().collect();
+        self.ambient_occlusion.draw(&mut builder, &views)?;
+
+        let clear_values = vec![
+            self.clear_color.into(),
+            self.depth_config.clear_value().into(),
+        ];
+        builder.begin_render_pass(self.target.framebuffer(), true, clear_values)?;
+        let sub_command_buffers = draw_views(
+            queue.clone(),
+            self.target.dimensions(),
+            &views,
+            &self.viewport_clear,
+            self.clear_color,
+            self.depth_config,
+            None,
+        );
+        // executing a secondary command buffer is unsafe for now
+        unsafe {
+            builder.execute_commands_from_vec(sub_command_buffers)?;
+        }
+        builder.end_render_pass()?;
+        builder.copy_image_to_buffer(self.target.color(), pixels.clone())?;
+
+        builder
+            .build()?
+            .execute(queue)?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
+
+        let pixels = pixels.read()?.to_vec();
+        Ok(pixels)
+    }
+}
@@ -1,7 +1,10 @@
 use std::sync::{Arc, Mutex};
 
 use winit::dpi::PhysicalSize;
-use winit::event::{DeviceEvent, ElementState, Event, MouseScrollDelta, WindowEvent};
+use winit::event::{
+    DeviceEvent, ElementState, Event, ModifiersState, MouseButton, MouseScrollDelta,
+    VirtualKeyCode, WindowEvent,
+};
 use winit::event_loop::ControlFlow;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -15,7 +18,21 @@ pub struct Input {
     pub move_forward_pressed: bool,
     pub move_backward_pressed: bool,
     pub cursor_offset: (f64, f64),
+    /// In window pixels from the top left, only meaningful while the cursor is not grabbed
+    pub cursor_position: (f64, f64),
+    pub left_mouse_pressed: bool,
+    pub right_mouse_pressed: bool,
+    pub middle_mouse_pressed: bool,
+    pub modifiers: ModifiersState,
     pub mouse_wheel_delta: f64,
+    /// Set for one poll when the key to switch to the next camera is pressed
+    pub next_camera: bool,
+    /// Set for one poll when the key to show or hide the stats overlay is pressed
+    pub toggle_stats: bool,
+    /// Set for one poll when the key to show or hide the GUI is pressed
+    pub toggle_gui: bool,
+    /// Set for one poll when the key to enter or leave the fractal explorer is pressed
+    pub toggle_fractal: bool,
     pub exiting: bool,
 }
 
@@ -31,14 +48,33 @@ impl Default for Input {
             move_forward_pressed: false,
             move_backward_pressed: false,
             cursor_offset: (0.0, 0.0),
+            cursor_position: (0.0, 0.0),
+            left_mouse_pressed: false,
+            right_mouse_pressed: false,
+            middle_mouse_pressed: false,
+            modifiers: ModifiersState::empty(),
             mouse_wheel_delta: 0.0,
+            next_camera: false,
+            toggle_stats: false,
+            toggle_gui: false,
+            toggle_fractal: false,
             exiting: false,
         }
     }
 }
 
+/// Window events kept in the order they arrived, for text entry and clicks that
+/// would be lost between two polls of `Input`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Character(char),
+    Key { key: VirtualKeyCode, pressed: bool },
+    MouseButton { button: MouseButton, pressed: bool },
+}
+
 pub struct InputHandler {
     input: Input,
+    events: Vec<InputEvent>,
     should_request_exit: bool,
 }
 
@@ -46,6 +82,7 @@ impl InputHandler {
     pub fn new() -> Arc<Mutex<InputHandler>> {
         Arc::new(Mutex::new(InputHandler {
             input: Input::default(),
+            events: vec![],
             should_request_exit: false,
         }))
     }
@@ -62,17 +99,39 @@ impl InputHandler {
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::Focused(is_focused) => self.input.focused = is_focused,
                 WindowEvent::Resized(size) => self.input.resized = Some(size),
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.input.cursor_position = (position.x, position.y)
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let pressed = state == ElementState::Pressed;
+                    match button {
+                        MouseButton::Left => self.input.left_mouse_pressed = pressed,
+                        MouseButton::Right => self.input.right_mouse_pressed = pressed,
+                        MouseButton::Middle => self.input.middle_mouse_pressed = pressed,
+                        MouseButton::Other(_) => (),
+                    }
+                    self.events
+                        .push(InputEvent::MouseButton { button, pressed });
+                }
+                WindowEvent::ModifiersChanged(modifiers) => self.input.modifiers = modifiers,
+                WindowEvent::ReceivedCharacter(c) => self.events.push(InputEvent::Character(c)),
+                WindowEvent::KeyboardInput { input, .. } => {
+                    if let Some(key) = input.virtual_keycode {
+                        self.events.push(InputEvent::Key {
+                            key,
+                            pressed: input.state == ElementState::Pressed,
+                        });
+                    }
+                }
                 _ => (),
             },
             Event::DeviceEvent { event, .. } => match event {
                 DeviceEvent::Key(input) => {
                     match input.scancode {
-                        1 => {
-                            // escape
-                            if self.input.focused {
-                                self.input.exiting = true;
-                                *control_flow = ControlFlow::Exit;
-                            }
+                        // escape
+                        1 if self.input.focused => {
+                            self.input.exiting = true;
+                            *control_flow = ControlFlow::Exit;
                         }
                         17 => {
                             self.input.move_forward_pressed = input.state == ElementState::Pressed
@@ -84,6 +143,14 @@ impl InputHandler {
                         32 => self.input.move_right_pressed = input.state == ElementState::Pressed, // d
                         57 => self.input.move_up_pressed = input.state == ElementState::Pressed, // space
                         42 => self.input.move_down_pressed = input.state == ElementState::Pressed, // shift
+                        15 if input.state == ElementState::Pressed => self.input.next_camera = true, // tab
+                        59 if input.state == ElementState::Pressed => {
+                            self.input.toggle_stats = true
+                        } // f1
+                        60 if input.state == ElementState::Pressed => self.input.toggle_gui = true, // f2
+                        61 if input.state == ElementState::Pressed => {
+                            self.input.toggle_fractal = true
+                        } // f3
                         _ => (),
                     }
                 }
@@ -110,9 +177,18 @@ impl InputHandler {
         let ret = self.input;
         self.input.cursor_offset = (0.0, 0.0); // reset offset
         self.input.mouse_wheel_delta = 0.0;
+        self.input.next_camera = false;
+        self.input.toggle_stats = false;
+        self.input.toggle_gui = false;
+        self.input.toggle_fractal = false;
         ret
     }
 
+    /// Takes the events that arrived since the last call
+    pub fn poll_events(&mut self) -> Vec<InputEvent> {
+        std::mem::take(&mut self.events)
+    }
+
     pub fn request_exit(&mut self) {
         self.should_request_exit = true;
     }
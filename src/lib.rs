@@ -1,13 +1,34 @@
 pub mod camera;
 pub mod context;
 pub mod controller;
+pub mod debug;
+pub mod depth;
 pub mod drawable;
+pub mod fog;
+pub mod font;
+pub mod fractal_explorer;
+pub mod gui;
+pub mod headless;
 pub mod input;
+pub mod light;
 pub mod material;
 pub mod mesh;
+pub mod outline;
+pub mod particles;
+pub mod picking;
+pub mod render_queue;
+pub mod render_target;
 pub mod renderer;
+pub mod resolution;
 pub mod scene;
+pub mod ssao;
+pub mod stats;
+pub mod text;
+pub mod texture;
+pub mod uniforms;
+pub mod upscale;
 pub mod utility;
+pub mod view;
 pub mod window;
 
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
@@ -15,6 +36,11 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
     pub uv: [f32; 2],
+    /// Points along increasing u, `w` is -1 when the bitangent points along decreasing v.
+    /// `mesh::tangents::generate_tangents` fills it in for meshes that lack them.
+    pub tangent: [f32; 4],
+    /// Linear RGBA, only read by materials that use vertex colors
+    pub color: [f32; 4],
 }
 
-vulkano::impl_vertex!(Vertex, position, normal, uv);
+vulkano::impl_vertex!(Vertex, position, normal, uv, tangent, color);
@@ -0,0 +1,21 @@
+use nalgebra_glm as glm;
+
+/// A light shining equally in every direction from `position`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+    pub position: glm::Vec3,
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        PointLight {
+            position: glm::vec3(0.0, 0.0, 0.0),
+            ambient: glm::vec3(0.2, 0.2, 0.2),
+            diffuse: glm::vec3(1.0, 1.0, 1.0),
+            specular: glm::vec3(1.0, 1.0, 1.0),
+        }
+    }
+}
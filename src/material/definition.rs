@@ -0,0 +1,573 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use vulkano::device::Queue;
+use vulkano::sampler::{Filter, MipmapMode, SamplerAddressMode};
+use vulkano::sync::GpuFuture;
+
+use super::pbr::{Pbr, PbrInput, PbrParameters};
+use super::phong::{MaterialAndFuture, Phong, PhongParameters};
+use super::pipelines::MaterialPipelines;
+use super::textured_phong::{TexturedPhong, TexturedPhongParameters};
+use super::toon::{InkOutline, Toon, ToonParameters, ToonShading};
+use super::unlit::{Unlit, UnlitParameters};
+use super::{BlendMode, Material};
+use crate::texture::{ColorSpace, ImageData, SamplerParameters, Texture, TextureError};
+use nalgebra_glm as glm;
+
+#[derive(Debug)]
+pub enum MaterialFileError {
+    IoError(PathBuf, io::Error),
+    ParseError(PathBuf, toml::de::Error),
+    /// A parameter was given both as a constant and as a map
+    ConflictingParameter(PathBuf, &'static str),
+    TextureError(PathBuf, TextureError),
+}
+
+impl fmt::Display for MaterialFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MaterialFileError::IoError(ref path, ref e) => write!(f, "{}: {}", path.display(), e),
+            MaterialFileError::ParseError(ref path, ref e) => {
+                write!(f, "{}: {}", path.display(), e)
+            }
+            MaterialFileError::ConflictingParameter(ref path, name) => write!(
+                f,
+                "{}: `{}` and `{}_map` can't both be given",
+                path.display(),
+                name,
+                name
+            ),
+            MaterialFileError::TextureError(ref path, ref e) => {
+                write!(f, "{}: {}", path.display(), e)
+            }
+        }
+    }
+}
+
+impl error::Error for MaterialFileError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            MaterialFileError::IoError(_, ref e) => Some(e),
+            MaterialFileError::ParseError(_, ref e) => Some(e),
+            MaterialFileError::ConflictingParameter(..) => None,
+            MaterialFileError::TextureError(_, ref e) => Some(e),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterDefinition {
+    Nearest,
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapDefinition {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+/// Applies to every map of the material
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplerDefinition {
+    pub filter: FilterDefinition,
+    /// Repeats when not given, but clamps to the edge for a toon ramp
+    pub wrap: Option<WrapDefinition>,
+    pub max_anisotropy: f32,
+}
+
+impl Default for SamplerDefinition {
+    fn default() -> Self {
+        SamplerDefinition {
+            filter: FilterDefinition::Linear,
+            wrap: None,
+            max_anisotropy: 1.0,
+        }
+    }
+}
+
+impl SamplerDefinition {
+    fn parameters(&self) -> SamplerParameters {
+        let (filter, mipmap_mode) = match self.filter {
+            FilterDefinition::Nearest => (Filter::Nearest, MipmapMode::Nearest),
+            FilterDefinition::Linear => (Filter::Linear, MipmapMode::Linear),
+        };
+        SamplerParameters {
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_mode,
+            address_mode: match self.wrap.unwrap_or(WrapDefinition::Repeat) {
+                WrapDefinition::Repeat => SamplerAddressMode::Repeat,
+                WrapDefinition::MirroredRepeat => SamplerAddressMode::MirroredRepeat,
+                WrapDefinition::ClampToEdge => SamplerAddressMode::ClampToEdge,
+            },
+            max_anisotropy: self.max_anisotropy,
+        }
+    }
+}
+
+fn white() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn opaque_white() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_shininess() -> f32 {
+    PhongParameters::default().shininess
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn default_bands() -> u32 {
+    3
+}
+
+fn default_rim_color() -> [f32; 3] {
+    ToonParameters::default().rim_color.into()
+}
+
+fn default_rim_width() -> f32 {
+    ToonParameters::default().rim_width
+}
+
+fn default_outline_width() -> f32 {
+    InkOutline::default().width
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PhongDefinition {
+    /// Already read to pick this definition
+    shading: Option<String>,
+    #[serde(default = "white")]
+    pub ambient: [f32; 3],
+    #[serde(default = "white")]
+    pub diffuse: [f32; 3],
+    #[serde(default = "white")]
+    pub specular: [f32; 3],
+    #[serde(default = "default_shininess")]
+    pub shininess: f32,
+    #[serde(default = "one")]
+    pub alpha: f32,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+impl PhongDefinition {
+    pub fn parameters(&self) -> PhongParameters {
+        PhongParameters {
+            ambient: self.ambient.into(),
+            diffuse: self.diffuse.into(),
+            specular: self.specular.into(),
+            shininess: self.shininess,
+            alpha: self.alpha,
+            blend_mode: self.blend,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TexturedPhongDefinition {
+    /// Already read to pick this definition
+    shading: Option<String>,
+    #[serde(default = "white")]
+    pub ambient: [f32; 3],
+    #[serde(default = "white")]
+    pub diffuse: [f32; 3],
+    #[serde(default = "white")]
+    pub specular: [f32; 3],
+    #[serde(default = "default_shininess")]
+    pub shininess: f32,
+    #[serde(default = "one")]
+    pub alpha: f32,
+    #[serde(default)]
+    pub blend: BlendMode,
+    pub diffuse_map: PathBuf,
+    pub specular_map: PathBuf,
+    pub normal_map: Option<PathBuf>,
+    #[serde(default)]
+    pub sampler: SamplerDefinition,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnlitDefinition {
+    /// Already read to pick this definition
+    shading: Option<String>,
+    #[serde(default = "opaque_white")]
+    pub color: [f32; 4],
+    pub color_map: Option<PathBuf>,
+    #[serde(default)]
+    pub vertex_colors: bool,
+    #[serde(default)]
+    pub sampler: SamplerDefinition,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+/// The light is stepped into `bands`, unless a `ramp_map` is given. An `outline_width`
+/// of 0 leaves out the outline.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToonDefinition {
+    /// Already read to pick this definition
+    shading: Option<String>,
+    #[serde(default = "white")]
+    pub color: [f32; 3],
+    #[serde(default = "default_bands")]
+    pub bands: u32,
+    pub ramp_map: Option<PathBuf>,
+    #[serde(default = "default_rim_color")]
+    pub rim_color: [f32; 3],
+    #[serde(default = "default_rim_width")]
+    pub rim_width: f32,
+    #[serde(default)]
+    pub outline_color: [f32; 3],
+    #[serde(default = "default_outline_width")]
+    pub outline_width: f32,
+    #[serde(default)]
+    pub sampler: SamplerDefinition,
+}
+
+/// Each parameter but the normal map is either a constant, such as `metallic = 1.0`,
+/// or a map, such as `metallic_map = "metal.png"`
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PbrDefinition {
+    /// Already read to pick this definition
+    shading: Option<String>,
+    pub base_color: Option<[f32; 4]>,
+    pub base_color_map: Option<PathBuf>,
+    pub metallic: Option<f32>,
+    pub metallic_map: Option<PathBuf>,
+    pub roughness: Option<f32>,
+    pub roughness_map: Option<PathBuf>,
+    pub emissive: Option<[f32; 3]>,
+    pub emissive_map: Option<PathBuf>,
+    pub occlusion: Option<f32>,
+    pub occlusion_map: Option<PathBuf>,
+    pub normal_map: Option<PathBuf>,
+    #[serde(default)]
+    pub sampler: SamplerDefinition,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+impl PbrDefinition {
+    fn conflict(&self) -> Option<&'static str> {
+        let pairs = [
+            (
+                "base_color",
+                self.base_color.is_some(),
+                &self.base_color_map,
+            ),
+            ("metallic", self.metallic.is_some(), &self.metallic_map),
+            ("roughness", self.roughness.is_some(), &self.roughness_map),
+            ("emissive", self.emissive.is_some(), &self.emissive_map),
+            ("occlusion", self.occlusion.is_some(), &self.occlusion_map),
+        ];
+        pairs
+            .iter()
+            .find(|(_, constant, map)| *constant && map.is_some())
+            .map(|(name, _, _)| *name)
+    }
+}
+
+/// The contents of a material file. The `shading` key picks the material, the other keys
+/// are its parameters, for example
+///
+/// ```toml
+/// shading = "pbr"
+/// base_color = [1.0, 0.77, 0.34, 1.0]
+/// metallic = 1.0
+/// roughness_map = "gold_roughness.png"
+/// blend = { mode = "alpha_test", cutoff = 0.5 }
+/// ```
+///
+/// Map paths are relative to the file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaterialDefinition {
+    Phong(PhongDefinition),
+    TexturedPhong(TexturedPhongDefinition),
+    Pbr(PbrDefinition),
+    Unlit(UnlitDefinition),
+    Toon(ToonDefinition),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Shading {
+    Phong,
+    TexturedPhong,
+    Pbr,
+    Unlit,
+    Toon,
+}
+
+/// Only the `shading` key of a material file, the rest is read once the definition is known
+#[derive(Deserialize)]
+struct ShadingKey {
+    shading: Shading,
+}
+
+impl MaterialDefinition {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, MaterialFileError> {
+        let path = path.as_ref();
+        let text =
+            fs::read_to_string(path).map_err(|e| MaterialFileError::IoError(path.to_owned(), e))?;
+        Self::parse(&text, path)
+    }
+
+    /// Reads the contents of a material file, `path` is only used in errors
+    pub fn parse(text: &str, path: &Path) -> Result<Self, MaterialFileError> {
+        // Each definition is read from the text itself rather than through a tagged enum,
+        // which serde buffers, so that errors keep the key and the line they are at
+        let parse_error = |e| MaterialFileError::ParseError(path.to_owned(), e);
+        let ShadingKey { shading } = toml::from_str(text).map_err(parse_error)?;
+        let definition = match shading {
+            Shading::Phong => MaterialDefinition::Phong(toml::from_str(text).map_err(parse_error)?),
+            Shading::TexturedPhong => {
+                MaterialDefinition::TexturedPhong(toml::from_str(text).map_err(parse_error)?)
+            }
+            Shading::Pbr => MaterialDefinition::Pbr(toml::from_str(text).map_err(parse_error)?),
+            Shading::Unlit => MaterialDefinition::Unlit(toml::from_str(text).map_err(parse_error)?),
+            Shading::Toon => MaterialDefinition::Toon(toml::from_str(text).map_err(parse_error)?),
+        };
+
+        if let MaterialDefinition::Pbr(ref pbr) = definition {
+            if let Some(name) = pbr.conflict() {
+                return Err(MaterialFileError::ConflictingParameter(
+                    path.to_owned(),
+                    name,
+                ));
+            }
+        }
+        Ok(definition)
+    }
+
+    /// Creates the material, loading its maps relative to `directory`
+    pub fn build(
+        &self,
+        directory: &Path,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<dyn Material + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let load = |path: &Path, color_space: ColorSpace, sampler: &SamplerDefinition| {
+            load_texture(&directory.join(path), color_space, sampler, queue.clone())
+        };
+        let load_optional = |path: &Option<PathBuf>, color_space, sampler| match *path {
+            Some(ref path) => load(path, color_space, sampler).map(Some),
+            None => Ok(None),
+        };
+
+        Ok(match *self {
+            MaterialDefinition::Phong(ref phong) => {
+                let (material, future) = Phong::new(phong.parameters(), pipelines, queue)?;
+                (material as Arc<dyn Material + Send + Sync>, future)
+            }
+            MaterialDefinition::TexturedPhong(ref textured) => {
+                let sampler = &textured.sampler;
+                let parameters = TexturedPhongParameters {
+                    phong: PhongParameters {
+                        ambient: textured.ambient.into(),
+                        diffuse: textured.diffuse.into(),
+                        specular: textured.specular.into(),
+                        shininess: textured.shininess,
+                        alpha: textured.alpha,
+                        blend_mode: textured.blend,
+                    },
+                    diffuse_map: load(&textured.diffuse_map, ColorSpace::Srgb, sampler)?,
+                    specular_map: load(&textured.specular_map, ColorSpace::Linear, sampler)?,
+                    normal_map: load_optional(&textured.normal_map, ColorSpace::Linear, sampler)?,
+                };
+                let (material, future) = TexturedPhong::new(parameters, pipelines, queue.clone())?;
+                (material as Arc<dyn Material + Send + Sync>, future)
+            }
+            MaterialDefinition::Pbr(ref pbr) => {
+                let sampler = &pbr.sampler;
+                let defaults = PbrParameters::default();
+                let parameters = PbrParameters {
+                    base_color: pbr_input(
+                        pbr.base_color.map(glm::Vec4::from),
+                        load_optional(&pbr.base_color_map, ColorSpace::Srgb, sampler)?,
+                        defaults.base_color,
+                    ),
+                    metallic: pbr_input(
+                        pbr.metallic,
+                        load_optional(&pbr.metallic_map, ColorSpace::Linear, sampler)?,
+                        defaults.metallic,
+                    ),
+                    roughness: pbr_input(
+                        pbr.roughness,
+                        load_optional(&pbr.roughness_map, ColorSpace::Linear, sampler)?,
+                        defaults.roughness,
+                    ),
+                    emissive: pbr_input(
+                        pbr.emissive.map(glm::Vec3::from),
+                        load_optional(&pbr.emissive_map, ColorSpace::Srgb, sampler)?,
+                        defaults.emissive,
+                    ),
+                    occlusion: pbr_input(
+                        pbr.occlusion,
+                        load_optional(&pbr.occlusion_map, ColorSpace::Linear, sampler)?,
+                        defaults.occlusion,
+                    ),
+                    normal_map: load_optional(&pbr.normal_map, ColorSpace::Linear, sampler)?,
+                    blend_mode: pbr.blend,
+                };
+                let (material, future) = Pbr::new(parameters, pipelines, queue.clone())?;
+                (material as Arc<dyn Material + Send + Sync>, future)
+            }
+            MaterialDefinition::Unlit(ref unlit) => {
+                let parameters = UnlitParameters {
+                    color: unlit.color.into(),
+                    color_map: load_optional(&unlit.color_map, ColorSpace::Srgb, &unlit.sampler)?,
+                    vertex_colors: unlit.vertex_colors,
+                    blend_mode: unlit.blend,
+                };
+                let (material, future) = Unlit::new(parameters, pipelines, queue.clone())?;
+                (material as Arc<dyn Material + Send + Sync>, future)
+            }
+            MaterialDefinition::Toon(ref toon) => {
+                let parameters = ToonParameters {
+                    color: toon.color.into(),
+                    shading: match toon.ramp_map {
+                        Some(ref path) => {
+                            // repeating would bleed the lit end into the unlit one
+                            let sampler = SamplerDefinition {
+                                wrap: Some(
+                                    toon.sampler.wrap.unwrap_or(WrapDefinition::ClampToEdge),
+                                ),
+                                ..toon.sampler
+                            };
+                            ToonShading::Ramp(load(path, ColorSpace::Srgb, &sampler)?)
+                        }
+                        None => ToonShading::Bands(toon.bands),
+                    },
+                    rim_color: toon.rim_color.into(),
+                    rim_width: toon.rim_width,
+                    outline: if toon.outline_width > 0.0 {
+                        Some(InkOutline {
+                            color: toon.outline_color.into(),
+                            width: toon.outline_width,
+                        })
+                    } else {
+                        None
+                    },
+                };
+                let (material, future) = Toon::new(parameters, pipelines, queue.clone())?;
+                (material as Arc<dyn Material + Send + Sync>, future)
+            }
+        })
+    }
+}
+
+fn pbr_input<T>(
+    constant: Option<T>,
+    map: Option<Arc<Texture>>,
+    default: PbrInput<T>,
+) -> PbrInput<T> {
+    match (map, constant) {
+        (Some(map), _) => PbrInput::Map(map),
+        (None, Some(constant)) => PbrInput::Constant(constant),
+        (None, None) => default,
+    }
+}
+
+/// Loads a map and waits for it to be uploaded
+fn load_texture(
+    path: &Path,
+    color_space: ColorSpace,
+    sampler: &SamplerDefinition,
+    queue: Arc<Queue>,
+) -> Result<Arc<Texture>, Box<dyn error::Error + Send + Sync>> {
+    let data =
+        ImageData::open(path).map_err(|e| MaterialFileError::TextureError(path.to_owned(), e))?;
+    let (texture, future) = Texture::new(&data, color_space, &sampler.parameters(), queue)?;
+    future.then_signal_fence_and_flush()?.wait(None)?;
+    Ok(texture)
+}
+
+/// Reads the material file at `path` and creates the material it describes
+pub fn load_material<P: AsRef<Path>>(
+    path: P,
+    pipelines: &MaterialPipelines,
+    queue: Arc<Queue>,
+) -> Result<MaterialAndFuture<dyn Material + Send + Sync>, Box<dyn error::Error + Send + Sync>> {
+    let path = path.as_ref();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    MaterialDefinition::open(path)?.build(directory, pipelines, queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<MaterialDefinition, MaterialFileError> {
+        MaterialDefinition::parse(text, Path::new("test.toml"))
+    }
+
+    fn parse_error(text: &str) -> String {
+        match parse(text) {
+            Err(e @ MaterialFileError::ParseError(..)) => e.to_string(),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unknown_field_is_named() {
+        // toml gives the position of the table for these, not of the key
+        let error = parse_error("shading = \"phong\"\nshiny = 3.0\n");
+        assert!(error.contains("unknown field `shiny`"), "{}", error);
+    }
+
+    #[test]
+    fn type_mismatch_names_the_key_and_line() {
+        let error = parse_error("shading = \"pbr\"\nroughness = 0.5\nmetallic = \"x\"\n");
+        assert!(error.contains("invalid type: string \"x\""), "{}", error);
+        assert!(error.contains("`metallic`"), "{}", error);
+        assert!(error.contains("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn unknown_shading_is_rejected() {
+        let error = parse_error("shading = \"glossy\"\n");
+        assert!(error.contains("unknown variant `glossy`"), "{}", error);
+        assert!(error.contains("`shading`"), "{}", error);
+    }
+
+    #[test]
+    fn constant_and_map_conflict() {
+        match parse("shading = \"pbr\"\nmetallic = 1.0\nmetallic_map = \"metal.png\"\n") {
+            Err(MaterialFileError::ConflictingParameter(_, name)) => assert_eq!(name, "metallic"),
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bundled_materials_parse() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("materials");
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(e) = MaterialDefinition::open(&path) {
+                panic!("{}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use super::phong::{self, MaterialAndFuture};
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+use nalgebra_glm as glm;
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/fractal.frag"
+    }
+}
+
+/// The size of the shorter side of the view at a zoom of 1
+const BASE_EXTENT: f64 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FractalKind {
+    Mandelbrot,
+    /// The set of starting points that stay bounded when iterated with the constant `c`
+    Julia {
+        c: glm::DVec2,
+    },
+}
+
+/// A cosine palette, `offset + amplitude * cos(2 pi (frequency * t + phase))`, with `t`
+/// running from 0 for points that escape at once to 1 for those that never do
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FractalPalette {
+    pub offset: glm::Vec3,
+    pub amplitude: glm::Vec3,
+    pub frequency: glm::Vec3,
+    pub phase: glm::Vec3,
+}
+
+impl Default for FractalPalette {
+    fn default() -> Self {
+        FractalPalette {
+            offset: glm::vec3(0.5, 0.5, 0.5),
+            amplitude: glm::vec3(0.5, 0.5, 0.5),
+            frequency: glm::vec3(4.0, 4.0, 4.0),
+            phase: glm::vec3(0.0, 0.33, 0.67),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FractalParameters {
+    pub kind: FractalKind,
+    pub center: glm::DVec2,
+    /// 1 shows an area 3 units across, which fits the whole Mandelbrot set
+    pub zoom: f64,
+    pub max_iterations: u32,
+    /// Points further than this from the origin are taken to escape
+    pub escape_radius: f32,
+    pub palette: FractalPalette,
+}
+
+impl Default for FractalParameters {
+    fn default() -> Self {
+        FractalParameters {
+            kind: FractalKind::Mandelbrot,
+            center: glm::vec2(-0.5, 0.0),
+            zoom: 1.0,
+            max_iterations: 256,
+            escape_radius: 4.0,
+            palette: FractalPalette::default(),
+        }
+    }
+}
+
+impl FractalParameters {
+    /// The size of the area shown over a view of `dimensions`, with y pointing up
+    pub fn extent(&self, dimensions: [u32; 2]) -> glm::DVec2 {
+        let [width, height] = [dimensions[0] as f64, dimensions[1] as f64];
+        let units_per_pixel = BASE_EXTENT / self.zoom / width.min(height).max(1.0);
+        glm::vec2(width * units_per_pixel, -height * units_per_pixel)
+    }
+
+    /// The point under a pixel of a view of `dimensions`, in window pixels from the top left
+    pub fn point_at(&self, pixel: [f64; 2], dimensions: [u32; 2]) -> glm::DVec2 {
+        let uv = glm::vec2(
+            pixel[0] / dimensions[0] as f64 - 0.5,
+            pixel[1] / dimensions[1] as f64 - 0.5,
+        );
+        self.center + uv.component_mul(&self.extent(dimensions))
+    }
+
+    /// Moves the view along with a drag of `delta` pixels
+    pub fn pan(&mut self, delta: [f64; 2], dimensions: [u32; 2]) {
+        let extent = self.extent(dimensions);
+        self.center -= glm::vec2(
+            delta[0] / dimensions[0] as f64 * extent.x,
+            delta[1] / dimensions[1] as f64 * extent.y,
+        );
+    }
+
+    /// Zooms in by `factor`, or out below 1, keeping the point under `pixel` in place
+    pub fn zoom_at(&mut self, factor: f64, pixel: [f64; 2], dimensions: [u32; 2]) {
+        let anchor = self.point_at(pixel, dimensions);
+        self.zoom *= factor;
+        self.center += anchor - self.point_at(pixel, dimensions);
+    }
+}
+
+/// Shows a fractal over the mesh's UVs, unlit
+pub struct Fractal {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+impl Fractal {
+    pub fn new(
+        parameters: FractalParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(BlendMode::Opaque)?;
+
+        let extent = BASE_EXTENT / parameters.zoom;
+        let (kind, julia_c) = match parameters.kind {
+            FractalKind::Mandelbrot => (0, glm::vec2(0.0, 0.0)),
+            FractalKind::Julia { c } => (1, c),
+        };
+        let palette = parameters.palette;
+        let material_uniform_data = fs::ty::material_parameters {
+            fractal: fs::ty::Fractal {
+                center: [parameters.center.x as f32, parameters.center.y as f32],
+                extent: [extent as f32, -extent as f32],
+                julia_c: [julia_c.x as f32, julia_c.y as f32],
+                kind,
+                max_iterations: parameters.max_iterations as i32,
+                escape_radius: parameters.escape_radius,
+                palette_offset: palette.offset.into(),
+                palette_amplitude: palette.amplitude.into(),
+                palette_frequency: palette.frequency.into(),
+                palette_phase: palette.phase.into(),
+                _dummy0: [0; 12],
+                _dummy1: [0, 0, 0, 0],
+                _dummy2: [0, 0, 0, 0],
+                _dummy3: [0, 0, 0, 0],
+            },
+        };
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            material_uniform_data,
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_buffer(buffer)?
+                .build()?,
+        );
+
+        let fractal = Arc::new(Fractal {
+            pipeline,
+            material_descriptors,
+        });
+
+        Ok((fractal, future))
+    }
+}
+
+impl MaterialPipeline for Fractal {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        _blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = phong::vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(pipelines.depth_config().depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for Fractal {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Opaque
+    }
+}
@@ -1,14 +1,128 @@
+use serde::Deserialize;
 use std::sync::Arc;
-use vulkano::descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use vulkano::descriptor::DescriptorSet;
+use vulkano::pipeline::blend::{AttachmentBlend, BlendFactor, BlendOp};
 use vulkano::pipeline::GraphicsPipelineAbstract;
 
+use crate::render_target::RenderTarget;
+
+/// In material files a table such as `{ mode = "alpha_test", cutoff = 0.5 }`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    /// Opaque, but fragments with an alpha below `cutoff` are discarded
+    AlphaTest {
+        cutoff: f32,
+    },
+    AlphaBlend,
+    Additive,
+    /// Like `AlphaBlend`, but the color is already multiplied by alpha, lit materials
+    /// multiply it in after lighting
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Transparent materials are drawn after all opaque ones, back to front
+    pub fn is_transparent(&self) -> bool {
+        match *self {
+            BlendMode::Opaque | BlendMode::AlphaTest { .. } => false,
+            BlendMode::AlphaBlend | BlendMode::Additive | BlendMode::Premultiplied => true,
+        }
+    }
+
+    pub fn depth_write(&self) -> bool {
+        !self.is_transparent()
+    }
+
+    /// The alpha below which fragments get discarded, 0 disables the test
+    pub fn alpha_cutoff(&self) -> f32 {
+        match *self {
+            BlendMode::AlphaTest { cutoff } => cutoff,
+            _ => 0.0,
+        }
+    }
+
+    pub fn is_premultiplied(&self) -> bool {
+        *self == BlendMode::Premultiplied
+    }
+
+    pub fn attachment_blend(&self) -> AttachmentBlend {
+        let (color_source, color_destination, alpha_source, alpha_destination) = match *self {
+            BlendMode::Opaque | BlendMode::AlphaTest { .. } => {
+                return AttachmentBlend::pass_through()
+            }
+            BlendMode::AlphaBlend => return AttachmentBlend::alpha_blending(),
+            BlendMode::Additive => (
+                BlendFactor::SrcAlpha,
+                BlendFactor::One,
+                BlendFactor::Zero,
+                BlendFactor::One,
+            ),
+            BlendMode::Premultiplied => (
+                BlendFactor::One,
+                BlendFactor::OneMinusSrcAlpha,
+                BlendFactor::One,
+                BlendFactor::OneMinusSrcAlpha,
+            ),
+        };
+
+        AttachmentBlend {
+            enabled: true,
+            color_op: BlendOp::Add,
+            color_source,
+            color_destination,
+            alpha_op: BlendOp::Add,
+            alpha_source,
+            alpha_destination,
+            ..AttachmentBlend::pass_through()
+        }
+    }
+}
+
+/// A pipeline and its set at `uniforms::MATERIAL_SET` drawn after a material's own, over
+/// the same mesh and with the same sets before it
+#[derive(Clone)]
+pub struct MaterialPass {
+    pub pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    pub material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+/// A pipeline and its descriptor set at `uniforms::MATERIAL_SET`, the engine binds the
+/// sets before it, which the pipeline declares by including the shared shader files
+///
+/// Only `Phong` can change its parameters once created, the others keep them in immutable
+/// buffers and have to be created again.
 pub trait Material {
-    fn get_world_layout(&self) -> Arc<UnsafeDescriptorSetLayout>;
-    fn get_view_layout(&self) -> Arc<UnsafeDescriptorSetLayout>;
-    fn get_lighting_layout(&self) -> Arc<UnsafeDescriptorSetLayout>;
     fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync>;
     fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync>;
+    fn blend_mode(&self) -> BlendMode;
+
+    /// Whether the pipeline reads the lighting set. Materials that don't, such as unlit ones,
+    /// need not include `lighting.glsl`, and get an empty set bound in its place.
+    fn uses_lighting(&self) -> bool {
+        true
+    }
+
+    /// Drawn right after the material itself, such as the inverted hull of an ink outline
+    fn extra_pass(&self) -> Option<MaterialPass> {
+        None
+    }
+
+    /// Whether drawing this material reads from `target`, such objects are
+    /// left out when rendering into that target
+    fn samples(&self, _target: &RenderTarget) -> bool {
+        false
+    }
 }
 
+pub mod definition;
+pub mod fractal;
+pub mod pbr;
 pub mod phong;
+pub mod pipelines;
+pub mod screen;
+pub mod textured_phong;
+pub mod toon;
+pub mod unlit;
@@ -0,0 +1,221 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::{Dimensions, ImageViewAccess, ImmutableImage};
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::Sampler;
+use vulkano::sync::GpuFuture;
+
+use super::phong::{self, MaterialAndFuture};
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::texture::{self, Texture};
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+use nalgebra_glm as glm;
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/pbr.frag"
+    }
+}
+
+/// A parameter that is the same across the surface, or read from a map at the mesh's UVs
+#[derive(Clone)]
+pub enum PbrInput<T> {
+    Constant(T),
+    Map(Arc<Texture>),
+}
+
+impl<T: Copy> PbrInput<T> {
+    /// The factor the map is multiplied by, the map, which is `white` for constants,
+    /// and its sampler
+    fn resolve(
+        &self,
+        one: T,
+        white: &Arc<dyn ImageViewAccess + Send + Sync>,
+        sampler: &Arc<Sampler>,
+    ) -> (T, Arc<dyn ImageViewAccess + Send + Sync>, Arc<Sampler>) {
+        match *self {
+            PbrInput::Constant(value) => (value, white.clone(), sampler.clone()),
+            PbrInput::Map(ref map) => (one, map.image(), map.sampler()),
+        }
+    }
+}
+
+/// Metallic-roughness parameters as in glTF. Maps of colors should be `ColorSpace::Srgb`.
+/// Metallic is read from the blue channel, roughness from green, and occlusion from red.
+#[derive(Clone)]
+pub struct PbrParameters {
+    pub base_color: PbrInput<glm::Vec4>,
+    pub metallic: PbrInput<f32>,
+    pub roughness: PbrInput<f32>,
+    pub emissive: PbrInput<glm::Vec3>,
+    /// Of the ambient light, on top of the screen-space ambient occlusion
+    pub occlusion: PbrInput<f32>,
+    /// In tangent space and `ColorSpace::Linear`, leaves the normals alone when `None`
+    pub normal_map: Option<Arc<Texture>>,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for PbrParameters {
+    fn default() -> Self {
+        PbrParameters {
+            base_color: PbrInput::Constant(glm::vec4(1.0, 1.0, 1.0, 1.0)),
+            metallic: PbrInput::Constant(0.0),
+            roughness: PbrInput::Constant(0.5),
+            emissive: PbrInput::Constant(glm::vec3(0.0, 0.0, 0.0)),
+            occlusion: PbrInput::Constant(1.0),
+            normal_map: None,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+/// Cook-Torrance with the GGX distribution, lit by the same light as `Phong`
+pub struct Pbr {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+    blend_mode: BlendMode,
+}
+
+impl Pbr {
+    pub fn new(
+        parameters: PbrParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(parameters.blend_mode)?;
+
+        let (white, future) = ImmutableImage::from_iter(
+            [255u8; 4].iter().cloned(),
+            Dimensions::Dim2d {
+                width: 1,
+                height: 1,
+            },
+            Format::R8G8B8A8Unorm,
+            queue.clone(),
+        )?;
+        future.then_signal_fence_and_flush()?.wait(None)?;
+        let white: Arc<dyn ImageViewAccess + Send + Sync> = white;
+
+        let sampler = Sampler::simple_repeat_linear(pipelines.device());
+
+        let (base_color, base_color_map, base_color_sampler) =
+            parameters
+                .base_color
+                .resolve(glm::vec4(1.0, 1.0, 1.0, 1.0), &white, &sampler);
+        let (metallic, metallic_map, metallic_sampler) =
+            parameters.metallic.resolve(1.0, &white, &sampler);
+        let (roughness, roughness_map, roughness_sampler) =
+            parameters.roughness.resolve(1.0, &white, &sampler);
+        let (emissive, emissive_map, emissive_sampler) =
+            parameters
+                .emissive
+                .resolve(glm::vec3(1.0, 1.0, 1.0), &white, &sampler);
+        let (occlusion, occlusion_map, occlusion_sampler) =
+            parameters.occlusion.resolve(1.0, &white, &sampler);
+        let normal_map = match parameters.normal_map {
+            Some(ref map) => map.clone(),
+            None => texture::flat_normal_map(queue.clone())?,
+        };
+
+        let material_uniform_data = fs::ty::material_parameters {
+            material: fs::ty::Material {
+                base_color: base_color.into(),
+                emissive: emissive.into(),
+                metallic,
+                roughness,
+                occlusion,
+                alpha_cutoff: parameters.blend_mode.alpha_cutoff(),
+                premultiply: parameters.blend_mode.is_premultiplied() as i32,
+            },
+        };
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            material_uniform_data,
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_buffer(buffer)?
+                .add_sampled_image(base_color_map, base_color_sampler)?
+                .add_sampled_image(metallic_map, metallic_sampler)?
+                .add_sampled_image(roughness_map, roughness_sampler)?
+                .add_sampled_image(emissive_map, emissive_sampler)?
+                .add_sampled_image(occlusion_map, occlusion_sampler)?
+                .add_sampled_image(normal_map.image(), normal_map.sampler())?
+                .build()?,
+        );
+
+        let pbr = Arc::new(Pbr {
+            pipeline,
+            material_descriptors,
+            blend_mode: parameters.blend_mode,
+        });
+
+        Ok((pbr, future))
+    }
+}
+
+impl MaterialPipeline for Pbr {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = phong::vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(
+                    pipelines
+                        .depth_config()
+                        .depth_stencil(blend_mode.depth_write()),
+                )
+                .blend_collective(blend_mode.attachment_blend())
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for Pbr {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+}
@@ -1,19 +1,23 @@
 use std::error;
-use std::sync::Arc;
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::buffer::{BufferAccess, BufferUsage, CpuBufferPool, ImmutableBuffer};
 use vulkano::command_buffer::AutoCommandBuffer;
 use vulkano::command_buffer::CommandBufferExecFuture;
 use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
-use vulkano::descriptor::descriptor_set::UnsafeDescriptorSetLayout;
 use vulkano::descriptor::DescriptorSet;
 use vulkano::descriptor::PipelineLayoutAbstract;
-use vulkano::device::{Device, Queue};
-use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
 use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
 use vulkano::sync::NowFuture;
 
-use super::Material;
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::uniforms::{self, MATERIAL_SET};
 use crate::Vertex;
 use nalgebra_glm as glm;
 
@@ -31,11 +35,78 @@ pub mod fs {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhongParameters {
+    pub ambient: glm::Vec3,
+    pub diffuse: glm::Vec3,
+    pub specular: glm::Vec3,
+    pub shininess: f32,
+    pub alpha: f32,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for PhongParameters {
+    fn default() -> Self {
+        PhongParameters {
+            ambient: glm::vec3(1.0, 1.0, 1.0),
+            diffuse: glm::vec3(1.0, 1.0, 1.0),
+            specular: glm::vec3(1.0, 1.0, 1.0),
+            shininess: 32.0,
+            alpha: 1.0,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterError {
+    /// The pipeline was built for `from`
+    BlendModeChanged { from: BlendMode, to: BlendMode },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParameterError::BlendModeChanged { from, to } => write!(
+                f,
+                "Cannot change the blend mode of a material from {:?} to {:?}",
+                from, to
+            ),
+        }
+    }
+}
+
+impl error::Error for ParameterError {}
+
+/// Its parameters can be changed while it is in use, objects drawn with it show the change
+/// from the next frame
 pub struct Phong {
     pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    uniform_buffer_pool: CpuBufferPool<fs::ty::material_parameters>,
+    state: Mutex<PhongState>,
+}
+
+struct PhongState {
+    parameters: PhongParameters,
     material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
 }
 
+fn uniform_data(parameters: &PhongParameters) -> fs::ty::material_parameters {
+    fs::ty::material_parameters {
+        material: fs::ty::Material {
+            ambient: parameters.ambient.into(),
+            diffuse: parameters.diffuse.into(),
+            specular: parameters.specular.into(),
+            shininess: parameters.shininess,
+            alpha: parameters.alpha,
+            alpha_cutoff: parameters.blend_mode.alpha_cutoff(),
+            premultiply: parameters.blend_mode.is_premultiplied() as i32,
+            _dummy0: [0, 0, 0, 0],
+            _dummy1: [0, 0, 0, 0],
+        },
+    }
+}
+
 pub type MaterialAndFuture<M> = (
     Arc<M>,
     CommandBufferExecFuture<NowFuture, AutoCommandBuffer>,
@@ -43,61 +114,153 @@ pub type MaterialAndFuture<M> = (
 
 impl Phong {
     pub fn new(
+        parameters: PhongParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(parameters.blend_mode)?;
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            uniform_data(&parameters),
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+        let material_descriptors = material_descriptors(&pipeline, buffer)?;
+
+        let phong = Arc::new(Phong {
+            pipeline,
+            uniform_buffer_pool: CpuBufferPool::uniform_buffer(pipelines.device()),
+            state: Mutex::new(PhongState {
+                parameters,
+                material_descriptors,
+            }),
+        });
+
+        Ok((phong, future))
+    }
+
+    pub fn parameters(&self) -> PhongParameters {
+        self.lock().parameters
+    }
+
+    /// Sets all of the parameters. The blend mode is fixed by the pipeline, so it has to stay
+    /// the same, only the cutoff of an `AlphaTest` can be changed.
+    pub fn set_parameters(
+        &self,
+        parameters: PhongParameters,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let current = self.lock().parameters.blend_mode;
+        if mem::discriminant(&current) != mem::discriminant(&parameters.blend_mode) {
+            return Err(Box::new(ParameterError::BlendModeChanged {
+                from: current,
+                to: parameters.blend_mode,
+            }));
+        }
+        self.update(|current| *current = parameters)
+    }
+
+    pub fn set_ambient(
+        &self,
         ambient: glm::Vec3,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.update(|parameters| parameters.ambient = ambient)
+    }
+
+    pub fn set_diffuse(
+        &self,
         diffuse: glm::Vec3,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.update(|parameters| parameters.diffuse = diffuse)
+    }
+
+    pub fn set_specular(
+        &self,
         specular: glm::Vec3,
-        shininess: f32,
-        device: Arc<Device>,
-        queue: Arc<Queue>,
-        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
-    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.update(|parameters| parameters.specular = specular)
+    }
+
+    pub fn set_shininess(&self, shininess: f32) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.update(|parameters| parameters.shininess = shininess)
+    }
+
+    pub fn set_alpha(&self, alpha: f32) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.update(|parameters| parameters.alpha = alpha)
+    }
+
+    /// Writes the changed parameters to a new buffer from the pool, so frames still in flight
+    /// keep reading the old one, and swaps in a descriptor set for it
+    fn update<F: FnOnce(&mut PhongParameters)>(
+        &self,
+        change: F,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let mut state = self.lock();
+        let mut parameters = state.parameters;
+        change(&mut parameters);
+        if parameters == state.parameters {
+            return Ok(());
+        }
+
+        let buffer = self.uniform_buffer_pool.next(uniform_data(&parameters))?;
+        state.material_descriptors = material_descriptors(&self.pipeline, buffer)?;
+        state.parameters = parameters;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PhongState> {
+        self.state.lock().expect("could not lock Phong parameters")
+    }
+}
+
+fn material_descriptors<B>(
+    pipeline: &Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    buffer: B,
+) -> Result<Arc<dyn DescriptorSet + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+where
+    B: BufferAccess + Send + Sync + 'static,
+{
+    let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+    Ok(Arc::new(
+        PersistentDescriptorSet::start(layout.clone())
+            .add_buffer(buffer)?
+            .build()?,
+    ))
+}
+
+impl MaterialPipeline for Phong {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
         let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
         let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
 
-        let pipeline = Arc::new(
+        Ok(Arc::new(
             GraphicsPipeline::start()
                 .vertex_input_single_buffer::<Vertex>()
                 .vertex_shader(vs.main_entry_point(), ())
                 .triangle_list()
                 .viewports_dynamic_scissors_irrelevant(1)
                 .fragment_shader(fs.main_entry_point(), ())
-                .depth_stencil_simple_depth()
+                .depth_stencil(
+                    pipelines
+                        .depth_config()
+                        .depth_stencil(blend_mode.depth_write()),
+                )
+                .blend_collective(blend_mode.attachment_blend())
                 .front_face_counter_clockwise()
                 .cull_mode_back()
-                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
-                .build(device)?,
-        );
-
-        let material_uniform_data = fs::ty::material_parameters {
-            material: fs::ty::Material {
-                ambient: ambient.into(),
-                diffuse: diffuse.into(),
-                specular: specular.into(),
-                shininess,
-                _dummy0: [0, 0, 0, 0],
-                _dummy1: [0, 0, 0, 0],
-            },
-        };
-
-        let (buffer, future) = ImmutableBuffer::from_data(
-            material_uniform_data,
-            BufferUsage::uniform_buffer(),
-            queue,
-        )?;
-
-        let layout = pipeline.descriptor_set_layout(3).unwrap();
-        let material_descriptors = Arc::new(
-            PersistentDescriptorSet::start(layout.clone())
-                .add_buffer(buffer)?
-                .build()?,
-        );
-
-        let phong = Arc::new(Phong {
-            pipeline,
-            material_descriptors,
-        });
-
-        Ok((phong, future))
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
     }
 }
 
@@ -106,17 +269,10 @@ impl Material for Phong {
         self.pipeline.clone()
     }
     fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
-        self.material_descriptors.clone()
-    }
-
-    fn get_world_layout(&self) -> Arc<UnsafeDescriptorSetLayout> {
-        self.pipeline.descriptor_set_layout(1).unwrap().clone()
+        self.lock().material_descriptors.clone()
     }
 
-    fn get_view_layout(&self) -> Arc<UnsafeDescriptorSetLayout> {
-        self.pipeline.descriptor_set_layout(0).unwrap().clone()
-    }
-    fn get_lighting_layout(&self) -> Arc<UnsafeDescriptorSetLayout> {
-        self.pipeline.descriptor_set_layout(2).unwrap().clone()
+    fn blend_mode(&self) -> BlendMode {
+        self.lock().parameters.blend_mode
     }
 }
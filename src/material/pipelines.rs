@@ -0,0 +1,101 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::error;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use vulkano::device::Device;
+use vulkano::framebuffer::RenderPassAbstract;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+
+use super::BlendMode;
+use crate::depth::DepthConfig;
+
+/// A kind of material, whose instances all draw with the pipeline it builds for their
+/// blend mode and differ only by their descriptor sets. The pipeline should be built with
+/// `uniforms::material_layout`, so the engine's sets can be bound to it.
+pub trait MaterialPipeline: 'static {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>;
+}
+
+/// The parts of `BlendMode` that end up in the pipeline, alpha test cutoffs are uniforms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Blending {
+    Opaque,
+    AlphaBlend,
+    Additive,
+    Premultiplied,
+}
+
+impl From<BlendMode> for Blending {
+    fn from(blend_mode: BlendMode) -> Self {
+        match blend_mode {
+            BlendMode::Opaque | BlendMode::AlphaTest { .. } => Blending::Opaque,
+            BlendMode::AlphaBlend => Blending::AlphaBlend,
+            BlendMode::Additive => Blending::Additive,
+            BlendMode::Premultiplied => Blending::Premultiplied,
+        }
+    }
+}
+
+type PipelineMap = HashMap<(TypeId, Blending), Arc<dyn GraphicsPipelineAbstract + Send + Sync>>;
+
+/// The material pipelines built for one render pass and depth configuration, each is built
+/// the first time a material asks for it and shared from then on
+pub struct MaterialPipelines {
+    device: Arc<Device>,
+    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    depth: DepthConfig,
+    pipelines: Mutex<PipelineMap>,
+}
+
+impl MaterialPipelines {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+        depth: DepthConfig,
+    ) -> Arc<Self> {
+        Arc::new(MaterialPipelines {
+            device,
+            render_pass,
+            depth,
+            pipelines: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn device(&self) -> Arc<Device> {
+        self.device.clone()
+    }
+
+    pub fn render_pass(&self) -> Arc<dyn RenderPassAbstract + Send + Sync> {
+        self.render_pass.clone()
+    }
+
+    pub fn depth_config(&self) -> DepthConfig {
+        self.depth
+    }
+
+    /// The pipeline `M` draws with in `blend_mode`
+    pub fn get<M: MaterialPipeline>(
+        &self,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let key = (TypeId::of::<M>(), Blending::from(blend_mode));
+        if let Some(pipeline) = self.lock().get(&key) {
+            return Ok(pipeline.clone());
+        }
+
+        // built without the lock held, in case building takes other pipelines from here
+        let pipeline = M::build_pipeline(self, blend_mode)?;
+        Ok(self.lock().entry(key).or_insert(pipeline).clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PipelineMap> {
+        self.pipelines
+            .lock()
+            .expect("could not lock material pipelines")
+    }
+}
@@ -0,0 +1,164 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+use super::phong::MaterialAndFuture;
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::render_target::RenderTarget;
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+use nalgebra_glm as glm;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/normal.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/screen.frag"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenParameters {
+    pub tint: glm::Vec3,
+    /// The mesh's uvs are mapped to `uv * uv_scale + uv_offset` before sampling,
+    /// a mirror would use a scale of (-1, 1) and an offset of (1, 0)
+    pub uv_scale: glm::Vec2,
+    pub uv_offset: glm::Vec2,
+}
+
+impl Default for ScreenParameters {
+    fn default() -> Self {
+        ScreenParameters {
+            tint: glm::vec3(1.0, 1.0, 1.0),
+            uv_scale: glm::vec2(1.0, 1.0),
+            uv_offset: glm::vec2(0.0, 0.0),
+        }
+    }
+}
+
+/// Shows the color of a render target unlit, for monitors, mirrors, and portals
+pub struct Screen {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+    target: Arc<RenderTarget>,
+}
+
+impl Screen {
+    pub fn new(
+        parameters: ScreenParameters,
+        target: Arc<RenderTarget>,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(BlendMode::Opaque)?;
+
+        let sampler = Sampler::new(
+            pipelines.device(),
+            Filter::Linear,
+            Filter::Linear,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        let screen_uniform_data = fs::ty::screen_parameters {
+            tint: parameters.tint.into(),
+            uv_scale: parameters.uv_scale.into(),
+            uv_offset: parameters.uv_offset.into(),
+            _dummy0: [0, 0, 0, 0],
+        };
+
+        let (buffer, future) =
+            ImmutableBuffer::from_data(screen_uniform_data, BufferUsage::uniform_buffer(), queue)?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_sampled_image(target.color(), sampler)?
+                .add_buffer(buffer)?
+                .build()?,
+        );
+
+        let screen = Arc::new(Screen {
+            pipeline,
+            material_descriptors,
+            target,
+        });
+
+        Ok((screen, future))
+    }
+}
+
+impl MaterialPipeline for Screen {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        _blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            false,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(pipelines.depth_config().depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for Screen {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Opaque
+    }
+
+    fn uses_lighting(&self) -> bool {
+        false
+    }
+
+    fn samples(&self, target: &RenderTarget) -> bool {
+        std::ptr::eq(self.target.as_ref(), target)
+    }
+}
@@ -0,0 +1,155 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use super::phong::{self, MaterialAndFuture, PhongParameters};
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::texture::{self, Texture};
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/textured_shading.frag"
+    }
+}
+
+#[derive(Clone)]
+pub struct TexturedPhongParameters {
+    /// The colors tint the maps
+    pub phong: PhongParameters,
+    /// Also used for the ambient color, its alpha multiplies `PhongParameters::alpha`
+    pub diffuse_map: Arc<Texture>,
+    pub specular_map: Arc<Texture>,
+    /// In tangent space and `ColorSpace::Linear`, leaves the normals alone when `None`
+    pub normal_map: Option<Arc<Texture>>,
+}
+
+/// `Phong` with its diffuse and specular colors, and optionally its normals, read from
+/// textures at the mesh's UVs
+pub struct TexturedPhong {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+    blend_mode: BlendMode,
+}
+
+impl TexturedPhong {
+    pub fn new(
+        parameters: TexturedPhongParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let blend_mode = parameters.phong.blend_mode;
+        let pipeline = pipelines.get::<Self>(blend_mode)?;
+
+        let colors = parameters.phong;
+        let material_uniform_data = fs::ty::material_parameters {
+            material: fs::ty::Material {
+                ambient: colors.ambient.into(),
+                diffuse: colors.diffuse.into(),
+                specular: colors.specular.into(),
+                shininess: colors.shininess,
+                alpha: colors.alpha,
+                alpha_cutoff: blend_mode.alpha_cutoff(),
+                premultiply: blend_mode.is_premultiplied() as i32,
+                _dummy0: [0, 0, 0, 0],
+                _dummy1: [0, 0, 0, 0],
+            },
+        };
+
+        let normal_map = match parameters.normal_map {
+            Some(ref map) => map.clone(),
+            None => texture::flat_normal_map(queue.clone())?,
+        };
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            material_uniform_data,
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_buffer(buffer)?
+                .add_sampled_image(
+                    parameters.diffuse_map.image(),
+                    parameters.diffuse_map.sampler(),
+                )?
+                .add_sampled_image(
+                    parameters.specular_map.image(),
+                    parameters.specular_map.sampler(),
+                )?
+                .add_sampled_image(normal_map.image(), normal_map.sampler())?
+                .build()?,
+        );
+
+        let textured_phong = Arc::new(TexturedPhong {
+            pipeline,
+            material_descriptors,
+            blend_mode,
+        });
+
+        Ok((textured_phong, future))
+    }
+}
+
+impl MaterialPipeline for TexturedPhong {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = phong::vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(
+                    pipelines
+                        .depth_config()
+                        .depth_stencil(blend_mode.depth_write()),
+                )
+                .blend_collective(blend_mode.attachment_blend())
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for TexturedPhong {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+}
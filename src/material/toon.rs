@@ -0,0 +1,247 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use super::phong::{self, MaterialAndFuture};
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material, MaterialPass};
+use crate::texture::{self, Texture};
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+use nalgebra_glm as glm;
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/toon.frag"
+    }
+}
+
+pub mod outline_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/ink_outline.vert"
+    }
+}
+
+pub mod outline_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/ink_outline.frag"
+    }
+}
+
+#[derive(Clone)]
+pub enum ToonShading {
+    /// The diffuse term rounded up to this many flat steps, surfaces facing away from the
+    /// light get only the ambient color
+    Bands(u32),
+    /// Read across its width at the diffuse term, from facing away from the light on the
+    /// left to facing it on the right, and multiplied with the color. Its sampler should
+    /// clamp to the edge, or the ends bleed into each other.
+    Ramp(Arc<Texture>),
+}
+
+/// An inverted hull, the mesh pushed out along its normals with only its back faces drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InkOutline {
+    pub color: glm::Vec3,
+    /// In world units, the same whatever the scale of the object
+    pub width: f32,
+}
+
+impl Default for InkOutline {
+    fn default() -> Self {
+        InkOutline {
+            color: glm::vec3(0.0, 0.0, 0.0),
+            width: 0.03,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ToonParameters {
+    pub color: glm::Vec3,
+    pub shading: ToonShading,
+    /// Lit by the light's diffuse color, black leaves out the rim
+    pub rim_color: glm::Vec3,
+    /// How far in from the silhouette the rim reaches, from 0 to 1
+    pub rim_width: f32,
+    /// The hull needs smooth normals, where they are split it comes apart at the edges
+    pub outline: Option<InkOutline>,
+}
+
+impl Default for ToonParameters {
+    fn default() -> Self {
+        ToonParameters {
+            color: glm::vec3(1.0, 1.0, 1.0),
+            shading: ToonShading::Bands(3),
+            rim_color: glm::vec3(0.5, 0.5, 0.5),
+            rim_width: 0.2,
+            outline: Some(InkOutline::default()),
+        }
+    }
+}
+
+/// Cel shading, lit by the engine's light in flat bands or through a ramp, with a hard
+/// rim light and an optional ink outline
+pub struct Toon {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+    outline: Option<MaterialPass>,
+}
+
+impl Toon {
+    pub fn new(
+        parameters: ToonParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(BlendMode::Opaque)?;
+
+        let (bands, ramp) = match parameters.shading {
+            ToonShading::Bands(bands) => (bands.max(1) as i32, texture::white_map(queue.clone())?),
+            ToonShading::Ramp(ref ramp) => (0, ramp.clone()),
+        };
+        let outline = parameters.outline.unwrap_or_default();
+        let material_uniform_data = fs::ty::toon_parameters {
+            color: parameters.color.into(),
+            bands,
+            rim_color: parameters.rim_color.into(),
+            rim_width: parameters.rim_width,
+            outline_color: outline.color.into(),
+            outline_width: outline.width,
+        };
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            material_uniform_data,
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_buffer(buffer.clone())?
+                .add_sampled_image(ramp.image(), ramp.sampler())?
+                .build()?,
+        );
+
+        let outline = match parameters.outline {
+            Some(_) => {
+                let pipeline = pipelines.get::<InkOutlinePass>(BlendMode::Opaque)?;
+                let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+                let material_descriptors = Arc::new(
+                    PersistentDescriptorSet::start(layout.clone())
+                        .add_buffer(buffer)?
+                        .build()?,
+                );
+                Some(MaterialPass {
+                    pipeline,
+                    material_descriptors,
+                })
+            }
+            None => None,
+        };
+
+        let toon = Arc::new(Toon {
+            pipeline,
+            material_descriptors,
+            outline,
+        });
+
+        Ok((toon, future))
+    }
+}
+
+impl MaterialPipeline for Toon {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        _blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = phong::vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(pipelines.depth_config().depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+/// The outline's pipeline, which culls front faces instead of back ones
+struct InkOutlinePass;
+
+impl MaterialPipeline for InkOutlinePass {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        _blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = outline_vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = outline_fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            true,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(pipelines.depth_config().depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_front()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for Toon {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Opaque
+    }
+
+    fn extra_pass(&self) -> Option<MaterialPass> {
+        self.outline.clone()
+    }
+}
@@ -0,0 +1,162 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, ImmutableBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::shader::EntryPointAbstract;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use super::phong::MaterialAndFuture;
+use super::pipelines::{MaterialPipeline, MaterialPipelines};
+use super::{BlendMode, Material};
+use crate::texture::{self, Texture};
+use crate::uniforms::{self, MATERIAL_SET};
+use crate::Vertex;
+use nalgebra_glm as glm;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/unlit.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/unlit.frag"
+    }
+}
+
+/// The color is `color`, times the map if there is one, times the vertex colors if
+/// `vertex_colors` is set, so a solid color, a textured, and a vertex colored material
+/// are all an `Unlit`
+#[derive(Clone)]
+pub struct UnlitParameters {
+    /// Linear RGBA
+    pub color: glm::Vec4,
+    /// Should be `ColorSpace::Srgb`
+    pub color_map: Option<Arc<Texture>>,
+    /// Multiplies in `Vertex::color`
+    pub vertex_colors: bool,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for UnlitParameters {
+    fn default() -> Self {
+        UnlitParameters {
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            color_map: None,
+            vertex_colors: false,
+            blend_mode: BlendMode::Opaque,
+        }
+    }
+}
+
+/// Ignores the light and the fog entirely, for gizmos, UI geometry, and visualizing data
+pub struct Unlit {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    material_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+    blend_mode: BlendMode,
+}
+
+impl Unlit {
+    pub fn new(
+        parameters: UnlitParameters,
+        pipelines: &MaterialPipelines,
+        queue: Arc<Queue>,
+    ) -> Result<MaterialAndFuture<Self>, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = pipelines.get::<Self>(parameters.blend_mode)?;
+
+        let material_uniform_data = fs::ty::unlit_parameters {
+            color: parameters.color.into(),
+            vertex_colors: parameters.vertex_colors as i32,
+            alpha_cutoff: parameters.blend_mode.alpha_cutoff(),
+        };
+
+        let color_map = match parameters.color_map {
+            Some(ref map) => map.clone(),
+            None => texture::white_map(queue.clone())?,
+        };
+
+        let (buffer, future) = ImmutableBuffer::from_data(
+            material_uniform_data,
+            BufferUsage::uniform_buffer(),
+            queue,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(MATERIAL_SET).unwrap();
+        let material_descriptors = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_buffer(buffer)?
+                .add_sampled_image(color_map.image(), color_map.sampler())?
+                .build()?,
+        );
+
+        let unlit = Arc::new(Unlit {
+            pipeline,
+            material_descriptors,
+            blend_mode: parameters.blend_mode,
+        });
+
+        Ok((unlit, future))
+    }
+}
+
+impl MaterialPipeline for Unlit {
+    fn build_pipeline(
+        pipelines: &MaterialPipelines,
+        blend_mode: BlendMode,
+    ) -> Result<Arc<dyn GraphicsPipelineAbstract + Send + Sync>, Box<dyn error::Error + Send + Sync>>
+    {
+        let device = pipelines.device();
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let layout = uniforms::material_layout(
+            device.clone(),
+            vs.main_entry_point().layout(),
+            fs.main_entry_point().layout(),
+            false,
+        )?;
+
+        Ok(Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(
+                    pipelines
+                        .depth_config()
+                        .depth_stencil(blend_mode.depth_write()),
+                )
+                .blend_collective(blend_mode.attachment_blend())
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(pipelines.render_pass(), 0).unwrap())
+                .with_pipeline_layout(device, layout)?,
+        ))
+    }
+}
+
+impl Material for Unlit {
+    fn pipeline(&self) -> Arc<dyn GraphicsPipelineAbstract + Send + Sync> {
+        self.pipeline.clone()
+    }
+    fn material_descriptors(&self) -> Arc<dyn DescriptorSet + Send + Sync> {
+        self.material_descriptors.clone()
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    fn uses_lighting(&self) -> bool {
+        false
+    }
+}
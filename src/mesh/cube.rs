@@ -4,6 +4,7 @@ use std::sync::Arc;
 use vulkano::buffer::{BufferAccess, BufferUsage, CpuAccessibleBuffer, TypedBufferAccess};
 use vulkano::device::Device;
 
+use super::tangents::generate_tangents;
 use super::Mesh;
 
 #[derive(Clone)]
@@ -12,132 +13,181 @@ pub struct Cube {
     index_buffer: Arc<CpuAccessibleBuffer<[u32]>>,
 }
 
+// tangents are generated in `Cube::from_vertices`
 const VERTICES: [Vertex; 24] = [
     // first face - front (0 - 3)
     Vertex {
         position: [-1.0, -1.0, -1.0],
         normal: [0.0, 0.0, -1.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, -1.0, -1.0],
         normal: [0.0, 0.0, -1.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, 1.0, -1.0],
         normal: [0.0, 0.0, -1.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, -1.0],
         normal: [0.0, 0.0, -1.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     // second face - bottom (4 - 7)
     Vertex {
         position: [1.0, -1.0, -1.0],
         normal: [0.0, -1.0, 0.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, -1.0, -1.0],
         normal: [0.0, -1.0, 0.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, -1.0, 1.0],
         normal: [0.0, -1.0, 0.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, -1.0, 1.0],
         normal: [0.0, -1.0, 0.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     // third face - right (8 - 11)
     Vertex {
         position: [1.0, -1.0, -1.0],
         normal: [1.0, 0.0, 0.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, -1.0, 1.0],
         normal: [1.0, 0.0, 0.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, -1.0],
         normal: [1.0, 0.0, 0.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, 1.0],
         normal: [1.0, 0.0, 0.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     // fourth face - left (12 - 15)
     Vertex {
         position: [-1.0, -1.0, 1.0],
         normal: [-1.0, 0.0, 0.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, -1.0, -1.0],
         normal: [-1.0, 0.0, 0.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, 1.0, 1.0],
         normal: [-1.0, 0.0, 0.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, 1.0, -1.0],
         normal: [-1.0, 0.0, 0.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     // fifth face - back (16 - 19)
     Vertex {
         position: [1.0, -1.0, 1.0],
         normal: [0.0, 0.0, 1.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, -1.0, 1.0],
         normal: [0.0, 0.0, 1.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, 1.0],
         normal: [0.0, 0.0, 1.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, 1.0, 1.0],
         normal: [0.0, 0.0, 1.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     // sixth face - top (20 - 23)
     Vertex {
         position: [-1.0, 1.0, -1.0],
         normal: [0.0, 1.0, 0.0],
         uv: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, -1.0],
         normal: [0.0, 1.0, 0.0],
         uv: [1.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [-1.0, 1.0, 1.0],
         normal: [0.0, 1.0, 0.0],
         uv: [0.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, 1.0],
         normal: [0.0, 1.0, 0.0],
         uv: [1.0, 1.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+        color: [1.0, 1.0, 1.0, 1.0],
     },
 ];
 
@@ -148,11 +198,27 @@ const INDICES: [u32; 36] = [
 
 impl Cube {
     pub fn new(device: Arc<Device>) -> Arc<Self> {
+        Self::from_vertices(device, VERTICES)
+    }
+
+    /// Each corner colored by its position, black at (-1, -1, -1) and white at (1, 1, 1),
+    /// for materials that use vertex colors
+    pub fn rgb(device: Arc<Device>) -> Arc<Self> {
+        let mut vertices = VERTICES;
+        for vertex in vertices.iter_mut() {
+            let [x, y, z] = vertex.position;
+            vertex.color = [(x + 1.0) / 2.0, (y + 1.0) / 2.0, (z + 1.0) / 2.0, 1.0];
+        }
+        Self::from_vertices(device, vertices)
+    }
+
+    fn from_vertices(device: Arc<Device>, mut vertices: [Vertex; 24]) -> Arc<Self> {
+        generate_tangents(&mut vertices, &INDICES);
         let vertex_buffer = CpuAccessibleBuffer::from_iter(
             device.clone(),
             BufferUsage::all(),
             false,
-            VERTICES.iter().copied(),
+            vertices.iter().copied(),
         )
         .unwrap();
 
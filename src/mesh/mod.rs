@@ -1,12 +1,25 @@
 pub mod cube;
+pub mod sphere;
+pub mod tangents;
 
+use std::mem;
 use std::sync::Arc;
 
 use vulkano::buffer::{BufferAccess, TypedBufferAccess};
 
+use crate::Vertex;
+
 pub trait Mesh {
     fn is_indexed(&self) -> bool;
 
     fn vertex_buffer(&self) -> Arc<dyn BufferAccess + Send + Sync>;
     fn index_buffer(&self) -> Arc<dyn TypedBufferAccess<Content = [u32]> + Send + Sync>;
+
+    fn triangle_count(&self) -> usize {
+        if self.is_indexed() {
+            self.index_buffer().len() / 3
+        } else {
+            self.vertex_buffer().size() / mem::size_of::<Vertex>() / 3
+        }
+    }
 }
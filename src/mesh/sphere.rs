@@ -0,0 +1,92 @@
+use crate::*;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferAccess, BufferUsage, CpuAccessibleBuffer, TypedBufferAccess};
+use vulkano::device::Device;
+
+use super::tangents::generate_tangents;
+use super::Mesh;
+
+/// A UV sphere of radius 1, with smooth normals
+#[derive(Clone)]
+pub struct Sphere {
+    vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex]>>,
+    index_buffer: Arc<CpuAccessibleBuffer<[u32]>>,
+}
+
+impl Sphere {
+    /// `sectors` around the y axis and `stacks` from pole to pole, the UVs wrap around once
+    pub fn new(device: Arc<Device>, sectors: u32, stacks: u32) -> Arc<Self> {
+        let sectors = sectors.max(3);
+        let stacks = stacks.max(2);
+
+        let mut vertices = Vec::with_capacity(((sectors + 1) * (stacks + 1)) as usize);
+        for stack in 0..=stacks {
+            let theta = PI * stack as f32 / stacks as f32;
+            for sector in 0..=sectors {
+                let phi = 2.0 * PI * sector as f32 / sectors as f32;
+                let position = [
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                ];
+                vertices.push(Vertex {
+                    position,
+                    normal: position,
+                    uv: [
+                        sector as f32 / sectors as f32,
+                        1.0 - stack as f32 / stacks as f32,
+                    ],
+                    tangent: [0.0, 0.0, 0.0, 0.0],
+                    color: [1.0, 1.0, 1.0, 1.0],
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity((sectors * stacks * 6) as usize);
+        for stack in 0..stacks {
+            for sector in 0..sectors {
+                let top = stack * (sectors + 1) + sector;
+                let bottom = top + sectors + 1;
+                // the triangles touching the poles would have no area
+                if stack != 0 {
+                    indices.extend_from_slice(&[top, bottom, top + 1]);
+                }
+                if stack != stacks - 1 {
+                    indices.extend_from_slice(&[top + 1, bottom, bottom + 1]);
+                }
+            }
+        }
+
+        generate_tangents(&mut vertices, &indices);
+        let vertex_buffer = CpuAccessibleBuffer::from_iter(
+            device.clone(),
+            BufferUsage::all(),
+            false,
+            vertices.into_iter(),
+        )
+        .unwrap();
+
+        let index_buffer =
+            CpuAccessibleBuffer::from_iter(device, BufferUsage::all(), false, indices.into_iter())
+                .unwrap();
+        Arc::new(Sphere {
+            vertex_buffer,
+            index_buffer,
+        })
+    }
+}
+
+impl Mesh for Sphere {
+    fn is_indexed(&self) -> bool {
+        true
+    }
+
+    fn vertex_buffer(&self) -> Arc<dyn BufferAccess + Send + Sync> {
+        self.vertex_buffer.clone()
+    }
+    fn index_buffer(&self) -> Arc<dyn TypedBufferAccess<Content = [u32]> + Send + Sync> {
+        self.index_buffer.clone()
+    }
+}
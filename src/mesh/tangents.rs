@@ -0,0 +1,171 @@
+use nalgebra_glm as glm;
+
+use crate::Vertex;
+
+/// Fills in `Vertex::tangent` from the positions and UVs of the triangles in `indices`,
+/// for an unindexed mesh pass `0..vertices.len()`.
+///
+/// Like MikkTSpace, each triangle's tangent is weighted by the angle of its corner, so
+/// splitting a face into more triangles does not change the result, and is then made
+/// orthogonal to the vertex normal. Vertices are not split where the UVs are mirrored.
+pub fn generate_tangents(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut tangents = vec![glm::vec3(0.0, 0.0, 0.0); vertices.len()];
+    let mut bitangents = vec![glm::vec3(0.0, 0.0, 0.0); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let corners = [
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        ];
+        let position = |i: usize| glm::Vec3::from(vertices[corners[i]].position);
+        let uv = |i: usize| glm::Vec2::from(vertices[corners[i]].uv);
+
+        let edge1 = position(1) - position(0);
+        let edge2 = position(2) - position(0);
+        let delta_uv1 = uv(1) - uv(0);
+        let delta_uv2 = uv(2) - uv(0);
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+        if determinant.abs() < f32::EPSILON {
+            // no UVs to follow, the fallback below picks some tangent
+            continue;
+        }
+        let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / determinant;
+        let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / determinant;
+        if tangent.norm() < f32::EPSILON || bitangent.norm() < f32::EPSILON {
+            continue;
+        }
+        let (tangent, bitangent) = (tangent.normalize(), bitangent.normalize());
+
+        for i in 0..3 {
+            let to_next = position((i + 1) % 3) - position(i);
+            let to_previous = position((i + 2) % 3) - position(i);
+            if to_next.norm() < f32::EPSILON || to_previous.norm() < f32::EPSILON {
+                continue;
+            }
+            let angle = glm::angle(&to_next, &to_previous);
+            tangents[corners[i]] += tangent * angle;
+            bitangents[corners[i]] += bitangent * angle;
+        }
+    }
+
+    for (vertex, (tangent, bitangent)) in vertices
+        .iter_mut()
+        .zip(tangents.iter().zip(bitangents.iter()))
+    {
+        let normal = glm::Vec3::from(vertex.normal).normalize();
+        let mut tangent = tangent - normal * normal.dot(tangent);
+        if tangent.norm() < f32::EPSILON {
+            // any direction in the surface will do
+            let axis = if normal.x.abs() < 0.9 {
+                glm::vec3(1.0, 0.0, 0.0)
+            } else {
+                glm::vec3(0.0, 1.0, 0.0)
+            };
+            tangent = axis - normal * normal.dot(&axis);
+        }
+        let tangent = tangent.normalize();
+        let handedness = if normal.cross(&tangent).dot(bitangent) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        vertex.tangent = [tangent.x, tangent.y, tangent.z, handedness];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Z: [f32; 3] = [0.0, 0.0, 1.0];
+
+    fn vertex(position: [f32; 3], normal: [f32; 3], uv: [f32; 2]) -> Vertex {
+        Vertex {
+            position,
+            normal,
+            uv,
+            tangent: [0.0, 0.0, 0.0, 0.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    fn assert_close(actual: [f32; 4], expected: [f32; 4]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected.iter())
+                .all(|(a, b)| (a - b).abs() < 1e-5),
+            "got {:?}, expected {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// A unit quad in the xy plane, with `v` running up or down it
+    fn quad(v: fn(f32) -> f32) -> Vec<Vertex> {
+        [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+            .iter()
+            .map(|&[x, y]| vertex([x, y, 0.0], Z, [x, v(y)]))
+            .collect()
+    }
+
+    #[test]
+    fn quad_tangent_follows_u() {
+        let mut vertices = quad(|y| y);
+        generate_tangents(&mut vertices, &[0, 1, 2, 0, 2, 3]);
+        for vertex in &vertices {
+            assert_close(vertex.tangent, [1.0, 0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn mirrored_v_flips_handedness() {
+        let mut vertices = quad(|y| 1.0 - y);
+        generate_tangents(&mut vertices, &[0, 1, 2, 0, 2, 3]);
+        for vertex in &vertices {
+            assert_close(vertex.tangent, [1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+
+    #[test]
+    fn splitting_a_face_keeps_the_tangents() {
+        // two quads meeting at the origin, whose UVs run along x on one and along y on the
+        // other, the second is split either across or through the shared corner
+        let vertices = vec![
+            vertex([0.0, 0.0, 0.0], Z, [0.0, 0.0]),
+            vertex([1.0, 0.0, 0.0], Z, [1.0, 0.0]),
+            vertex([1.0, 1.0, 0.0], Z, [1.0, 1.0]),
+            vertex([0.0, 1.0, 0.0], Z, [0.0, 1.0]),
+            vertex([0.0, 1.0, 0.0], Z, [1.0, 0.0]),
+            vertex([-1.0, 1.0, 0.0], Z, [1.0, 1.0]),
+            vertex([-1.0, 0.0, 0.0], Z, [0.0, 1.0]),
+        ];
+        let mut across = vertices.clone();
+        generate_tangents(&mut across, &[0, 1, 2, 0, 2, 3, 0, 4, 6, 4, 5, 6]);
+        let mut through = vertices;
+        generate_tangents(&mut through, &[0, 1, 2, 0, 2, 3, 0, 4, 5, 0, 5, 6]);
+
+        let diagonal = 0.5f32.sqrt();
+        assert_close(across[0].tangent, [diagonal, diagonal, 0.0, 1.0]);
+        for (across, through) in across.iter().zip(through.iter()) {
+            assert_close(across.tangent, through.tangent);
+        }
+    }
+
+    #[test]
+    fn zero_uv_area_still_gets_a_tangent() {
+        let normal = glm::vec3(1.0, 1.0, 1.0).normalize();
+        let mut vertices: Vec<Vertex> = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            .iter()
+            .map(|&position| vertex(position, normal.into(), [0.5, 0.5]))
+            .collect();
+        generate_tangents(&mut vertices, &[0, 1, 2]);
+        for vertex in &vertices {
+            let tangent = glm::vec3(vertex.tangent[0], vertex.tangent[1], vertex.tangent[2]);
+            assert!((tangent.norm() - 1.0).abs() < 1e-5, "{:?}", vertex.tangent);
+            assert!(tangent.dot(&normal).abs() < 1e-5, "{:?}", vertex.tangent);
+            assert_eq!(vertex.tangent[3].abs(), 1.0);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Queue;
+use vulkano::format::{ClearValue, Format};
+use vulkano::framebuffer::{Framebuffer, FramebufferAbstract, RenderPassAbstract, Subpass};
+use vulkano::image::AttachmentImage;
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::Sampler;
+
+use nalgebra_glm as glm;
+
+use crate::material::BlendMode;
+use crate::picking;
+use crate::renderer::SceneView;
+use crate::Vertex;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/fullscreen.vert"
+    }
+}
+
+pub mod mask_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/outline_mask.frag"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/outline.frag"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutlineStyle {
+    pub color: glm::Vec4,
+    /// In window pixels
+    pub width: u32,
+}
+
+impl Default for OutlineStyle {
+    fn default() -> Self {
+        OutlineStyle {
+            color: glm::vec4(1.0, 0.6, 0.0, 1.0),
+            width: 3,
+        }
+    }
+}
+
+type OutlinePipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+struct MaskTarget {
+    dimensions: [u32; 2],
+    framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+    mask_set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+/// Outlines the highlighted objects by drawing their silhouettes into a mask and then
+/// coloring the pixels within `OutlineStyle::width` of it. Outlines show through other objects.
+pub struct OutlineRenderer {
+    queue: Arc<Queue>,
+    mask_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    mask_pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    pipeline: Arc<OutlinePipeline>,
+    sampler: Arc<Sampler>,
+    style: OutlineStyle,
+    target: Option<MaskTarget>,
+}
+
+impl OutlineRenderer {
+    /// `render_pass` is the one the outlines are drawn over the finished image in
+    pub fn new(
+        queue: Arc<Queue>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let mask_vs =
+            picking::vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let mask_fs =
+            mask_fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let mask_pass = Arc::new(vulkano::single_pass_renderpass!(device.clone(),
+            attachments: {
+                mask: {
+                    load: Clear,
+                    store: Store,
+                    format: Format::R8Unorm,
+                    samples: 1,
+                }
+            },
+        pass: {
+            color: [mask],
+            depth_stencil: {}
+        })?);
+
+        let mask_pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(mask_vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(mask_fs.main_entry_point(), ())
+                .render_pass(Subpass::from(mask_pass.clone(), 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .blend_collective(BlendMode::AlphaBlend.attachment_blend())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let sampler = Sampler::simple_repeat_linear_no_mipmap(device);
+
+        Ok(OutlineRenderer {
+            queue,
+            mask_pass,
+            mask_pipeline,
+            pipeline,
+            sampler,
+            style: OutlineStyle::default(),
+            target: None,
+        })
+    }
+
+    pub fn style(&self) -> OutlineStyle {
+        self.style
+    }
+
+    pub fn set_style(&mut self, style: OutlineStyle) {
+        self.style = style;
+    }
+
+    fn target(
+        &mut self,
+        dimensions: [u32; 2],
+    ) -> Result<&MaskTarget, Box<dyn error::Error + Send + Sync>> {
+        let outdated = match self.target {
+            Some(ref target) => target.dimensions != dimensions,
+            None => true,
+        };
+        if outdated {
+            let mask =
+                AttachmentImage::sampled(self.queue.device().clone(), dimensions, Format::R8Unorm)?;
+            let framebuffer = Arc::new(
+                Framebuffer::start(self.mask_pass.clone())
+                    .add(mask.clone())?
+                    .build()?,
+            );
+            let layout = self.pipeline.descriptor_set_layout(0).unwrap();
+            let mask_set = Arc::new(
+                PersistentDescriptorSet::start(layout.clone())
+                    .add_sampled_image(mask, self.sampler.clone())?
+                    .build()?,
+            );
+            self.target = Some(MaskTarget {
+                dimensions,
+                framebuffer,
+                mask_set,
+            });
+        }
+        Ok(self.target.as_ref().unwrap())
+    }
+
+    /// Draws the highlighted objects of `views` into the mask, sized to the image the outlines
+    /// are later drawn over. Returns whether there was anything to outline.
+    pub fn draw_mask(
+        &mut self,
+        builder: &mut AutoCommandBufferBuilder,
+        dimensions: [u32; 2],
+        views: &[&SceneView],
+    ) -> Result<bool, Box<dyn error::Error + Send + Sync>> {
+        let highlighted = |view: &&SceneView| {
+            view.render_queue
+                .iter()
+                .any(|item| item.object.is_highlighted())
+        };
+        if !views.iter().any(highlighted) {
+            return Ok(false);
+        }
+
+        let pipeline = self.mask_pipeline.clone();
+        let target = self.target(dimensions)?;
+        builder.begin_render_pass(
+            target.framebuffer.clone(),
+            false,
+            vec![ClearValue::Float([0.0, 0.0, 0.0, 0.0])],
+        )?;
+        for view in views {
+            let dynamic_state = DynamicState {
+                viewports: Some(vec![view.rect.to_viewport(dimensions)]),
+                ..DynamicState::none()
+            };
+            for item in view.render_queue.iter() {
+                let object = item.object;
+                if !object.is_highlighted() {
+                    continue;
+                }
+                let mesh = object.get_mesh();
+                let parameters = picking::vs::ty::object_parameters {
+                    world: object.get_transform().into(),
+                    object_id: 0,
+                };
+                if mesh.is_indexed() {
+                    builder.draw_indexed(
+                        pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        mesh.index_buffer(),
+                        view.view_set.clone(),
+                        parameters,
+                    )?;
+                } else {
+                    builder.draw(
+                        pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        view.view_set.clone(),
+                        parameters,
+                    )?;
+                }
+            }
+        }
+        builder.end_render_pass()?;
+        Ok(true)
+    }
+
+    /// Colors the pixels around the mask drawn by the last `draw_mask`, inside a render pass
+    /// over the whole image
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let target = match self.target {
+            Some(ref target) => target,
+            None => return Ok(()),
+        };
+        let push_constants = fs::ty::outline_parameters {
+            color: self.style.color.into(),
+            width: self.style.width as i32,
+        };
+        builder.draw(
+            self.pipeline.clone(),
+            dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            target.mask_set.clone(),
+            push_constants,
+        )?;
+        Ok(())
+    }
+}
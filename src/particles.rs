@@ -0,0 +1,336 @@
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, DeviceLocalBuffer};
+use vulkano::command_buffer::{
+    AutoCommandBuffer, AutoCommandBufferBuilder, CommandBuffer, CommandBufferExecFuture,
+    DispatchError, DynamicState,
+};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::{Device, Queue};
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::{
+    ComputePipeline, ComputePipelineAbstract, GraphicsPipeline, GraphicsPipelineAbstract,
+};
+use vulkano::sync::NowFuture;
+
+use nalgebra_glm as glm;
+
+use crate::depth::DepthConfig;
+use crate::drawable::Drawable;
+use crate::material::BlendMode;
+
+pub mod cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "shaders/particles.comp"
+    }
+}
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/particle.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/particle.frag"
+    }
+}
+
+const WORKGROUP_SIZE: u32 = 64;
+// Longest frame spawned for in full, so a stall doesn't come out as a burst
+const MAX_SPAWN_DELTA: f32 = 0.1;
+
+type ParticlePipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+/// The pipelines shared by all emitters
+pub struct ParticleSystem {
+    simulate: Arc<dyn ComputePipelineAbstract + Send + Sync>,
+    pipeline: Arc<ParticlePipeline>,
+}
+
+impl ParticleSystem {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+        depth: DepthConfig,
+    ) -> Result<Arc<Self>, Box<dyn error::Error + Send + Sync>> {
+        let cs = cs::Shader::load(device.clone()).expect("failed to create shader module");
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let simulate = Arc::new(ComputePipeline::new(
+            device.clone(),
+            &cs.main_entry_point(),
+            &(),
+        )?);
+
+        // additive, so particles don't need sorting
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(depth.depth_stencil(false))
+                .blend_collective(BlendMode::Additive.attachment_blend())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device)?,
+        );
+
+        Ok(Arc::new(ParticleSystem { simulate, pipeline }))
+    }
+}
+
+#[derive(Debug)]
+pub enum EmitterError {
+    /// `max_particles` was 0
+    NoParticles,
+}
+
+impl fmt::Display for EmitterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmitterError::NoParticles => {
+                write!(f, "An emitter needs room for at least one particle")
+            }
+        }
+    }
+}
+
+impl error::Error for EmitterError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmitterParameters {
+    /// At least 1
+    pub max_particles: u32,
+    /// Particles spawned per second
+    pub spawn_rate: f32,
+    /// Longest life in seconds, each particle lives between half of this and all of it
+    pub lifetime: f32,
+    /// Initial velocity in the emitter's space
+    pub velocity: glm::Vec3,
+    /// Radius of the random offset added to the initial velocity
+    pub spread: f32,
+    /// Acceleration in world space
+    pub gravity: glm::Vec3,
+    /// Half the width of a billboard
+    pub size: f32,
+    pub start_color: glm::Vec4,
+    pub end_color: glm::Vec4,
+}
+
+impl Default for EmitterParameters {
+    fn default() -> Self {
+        EmitterParameters {
+            max_particles: 1024,
+            spawn_rate: 256.0,
+            lifetime: 2.0,
+            velocity: glm::vec3(0.0, 2.0, 0.0),
+            spread: 0.5,
+            gravity: glm::vec3(0.0, -1.0, 0.0),
+            size: 0.05,
+            start_color: glm::vec4(1.0, 0.8, 0.3, 1.0),
+            end_color: glm::vec4(1.0, 0.2, 0.0, 0.0),
+        }
+    }
+}
+
+/// Spawns particles at its scene graph node's origin, simulated and drawn on the GPU
+pub struct ParticleEmitter {
+    system: Arc<ParticleSystem>,
+    parameters: EmitterParameters,
+    transform: glm::Mat4,
+    simulate_set: Arc<dyn DescriptorSet + Send + Sync>,
+    particle_set: Arc<dyn DescriptorSet + Send + Sync>,
+    spawn_accumulator: f32,
+    spawn_cursor: u32,
+    step: cs::ty::simulation_parameters,
+}
+
+impl ParticleEmitter {
+    pub fn new(
+        system: Arc<ParticleSystem>,
+        parameters: EmitterParameters,
+        queue: Arc<Queue>,
+    ) -> Result<
+        (Self, CommandBufferExecFuture<NowFuture, AutoCommandBuffer>),
+        Box<dyn error::Error + Send + Sync>,
+    > {
+        if parameters.max_particles == 0 {
+            return Err(EmitterError::NoParticles.into());
+        }
+
+        let device = queue.device().clone();
+        let particles: Arc<DeviceLocalBuffer<[cs::ty::Particle]>> = DeviceLocalBuffer::array(
+            device.clone(),
+            parameters.max_particles as usize,
+            BufferUsage {
+                storage_buffer: true,
+                transfer_destination: true,
+                ..BufferUsage::none()
+            },
+            vec![queue.family()],
+        )?;
+
+        // zero remaining life marks every particle as dead
+        let mut builder =
+            AutoCommandBufferBuilder::primary_one_time_submit(device, queue.family())?;
+        builder.fill_buffer(particles.clone(), 0)?;
+        let future = builder.build()?.execute(queue)?;
+
+        let simulate_set = Arc::new(
+            PersistentDescriptorSet::start(
+                system.simulate.descriptor_set_layout(0).unwrap().clone(),
+            )
+            .add_buffer(particles.clone())?
+            .build()?,
+        );
+        let particle_set = Arc::new(
+            PersistentDescriptorSet::start(
+                system.pipeline.descriptor_set_layout(1).unwrap().clone(),
+            )
+            .add_buffer(particles)?
+            .build()?,
+        );
+
+        let mut emitter = ParticleEmitter {
+            system,
+            parameters,
+            transform: glm::identity(),
+            simulate_set,
+            particle_set,
+            spawn_accumulator: 0.0,
+            spawn_cursor: 0,
+            step: cs::ty::simulation_parameters {
+                origin: [0.0; 4],
+                velocity: [0.0; 4],
+                gravity: [0.0; 4],
+                delta_time: 0.0,
+                lifetime: parameters.lifetime,
+                spread: parameters.spread,
+                seed: 0,
+                spawn_start: 0,
+                spawn_count: 0,
+                particle_count: parameters.max_particles,
+            },
+        };
+        emitter.update(0.0);
+
+        Ok((emitter, future))
+    }
+
+    pub fn parameters(&self) -> EmitterParameters {
+        self.parameters
+    }
+
+    /// Changing `max_particles` has no effect after the emitter is created
+    pub fn set_parameters(&mut self, parameters: EmitterParameters) {
+        self.parameters = EmitterParameters {
+            max_particles: self.parameters.max_particles,
+            ..parameters
+        };
+    }
+
+    /// Two per particle, including dead ones
+    pub fn triangle_count(&self) -> usize {
+        self.parameters.max_particles as usize * 2
+    }
+
+    pub fn get_transform(&self) -> glm::Mat4 {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: glm::Mat4) {
+        self.transform = transform;
+    }
+
+    /// Advances the emitter's time, the simulation itself happens in the next `dispatch`
+    pub fn update(&mut self, delta_time: f32) {
+        let max_particles = self.parameters.max_particles;
+        self.spawn_accumulator += self.parameters.spawn_rate * delta_time.min(MAX_SPAWN_DELTA);
+        let spawn_count = (self.spawn_accumulator as u32).min(max_particles);
+        // whatever didn't fit is dropped rather than spawned over the next frames
+        self.spawn_accumulator = (self.spawn_accumulator - spawn_count as f32).min(1.0);
+
+        let origin = self.transform * glm::vec4(0.0, 0.0, 0.0, 1.0);
+        let velocity = self.transform
+            * glm::vec4(
+                self.parameters.velocity.x,
+                self.parameters.velocity.y,
+                self.parameters.velocity.z,
+                0.0,
+            );
+        let gravity = self.parameters.gravity;
+
+        self.step = cs::ty::simulation_parameters {
+            origin: origin.into(),
+            velocity: velocity.into(),
+            gravity: [gravity.x, gravity.y, gravity.z, 0.0],
+            delta_time,
+            lifetime: self.parameters.lifetime,
+            spread: self.parameters.spread,
+            seed: self.step.seed.wrapping_add(1),
+            spawn_start: self.spawn_cursor,
+            spawn_count,
+            particle_count: max_particles,
+        };
+        self.spawn_cursor = (self.spawn_cursor + spawn_count) % max_particles;
+    }
+
+    /// Records the simulation step, must be outside of a render pass
+    pub fn dispatch(&self, builder: &mut AutoCommandBufferBuilder) -> Result<(), DispatchError> {
+        let groups = self.parameters.max_particles.div_ceil(WORKGROUP_SIZE);
+        builder.dispatch(
+            [groups, 1, 1],
+            self.system.simulate.clone(),
+            self.simulate_set.clone(),
+            self.step,
+        )?;
+        Ok(())
+    }
+}
+
+impl Drawable for ParticleEmitter {
+    fn draw(
+        &self,
+        queue: Arc<Queue>,
+        dynamic_state: &DynamicState,
+        view_set: Arc<dyn DescriptorSet + Send + Sync>,
+        _lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
+    ) -> Result<AutoCommandBuffer, Box<dyn error::Error + Send + Sync>> {
+        let pipeline = self.system.pipeline.clone();
+        let mut builder = AutoCommandBufferBuilder::secondary_graphics(
+            pipeline.device().clone(),
+            queue.family(),
+            GraphicsPipelineAbstract::subpass(pipeline.clone()),
+        )?;
+        builder.draw(
+            pipeline,
+            dynamic_state,
+            BufferlessVertices {
+                vertices: self.parameters.max_particles as usize * 6,
+                instances: 1,
+            },
+            (view_set, self.particle_set.clone()),
+            vs::ty::particle_appearance {
+                start_color: self.parameters.start_color.into(),
+                end_color: self.parameters.end_color.into(),
+                size: self.parameters.size,
+            },
+        )?;
+        Ok(builder.build()?)
+    }
+}
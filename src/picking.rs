@@ -0,0 +1,298 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::cpu_access::ReadLockError;
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::command_buffer::{
+    AutoCommandBuffer, AutoCommandBufferBuilder, CommandBuffer, CommandBufferExecFuture,
+    DynamicState,
+};
+use vulkano::device::Queue;
+use vulkano::format::{ClearValue, Format};
+use vulkano::framebuffer::{Framebuffer, FramebufferAbstract, RenderPassAbstract, Subpass};
+use vulkano::image::{AttachmentImage, ImageUsage};
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sync::{FenceSignalFuture, GpuFuture, NowFuture};
+
+use crate::depth::DepthConfig;
+use crate::renderer::SceneView;
+use crate::scene::SceneGraph;
+use crate::Vertex;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/object_id.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/object_id.frag"
+    }
+}
+
+struct IdTarget {
+    dimensions: [u32; 2],
+    ids: Arc<AttachmentImage>,
+    framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+}
+
+enum PendingPick {
+    /// The position was outside of every view
+    Missed,
+    Drawing {
+        future: Box<FenceSignalFuture<CommandBufferExecFuture<NowFuture, AutoCommandBuffer>>>,
+        pixel: Arc<CpuAccessibleBuffer<[u32]>>,
+    },
+}
+
+/// Finds the scene graph node under a pixel by drawing every object's node index into
+/// an R32_UINT target and reading the pixel back.
+/// IDs are the index `SceneGraph::visit` gives a node plus one, zero is left where nothing was drawn.
+///
+/// The IDs are drawn in a pass of their own, only when something is picked, rather than into
+/// a second attachment of the scene pass, which would need every material to write them. Only
+/// opaque objects are drawn, with their meshes alone, so alpha tested holes and extra passes
+/// such as ink outlines can still be picked. The result is read back once the GPU is done,
+/// usually by the next frame, instead of waiting for it.
+pub struct ObjectIdRenderer {
+    queue: Arc<Queue>,
+    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    depth_config: DepthConfig,
+    target: Option<IdTarget>,
+    pending: Option<PendingPick>,
+}
+
+impl ObjectIdRenderer {
+    pub fn new(
+        queue: Arc<Queue>,
+        depth_config: DepthConfig,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let render_pass = Arc::new(vulkano::single_pass_renderpass!(device.clone(),
+            attachments: {
+                ids: {
+                    load: Clear,
+                    store: Store,
+                    format: Format::R32Uint,
+                    samples: 1,
+                },
+                depth: {
+                    load: Clear,
+                    store: DontCare,
+                    format: Format::D32Sfloat,
+                    samples: 1,
+                }
+            },
+        pass: {
+            color: [ids],
+            depth_stencil: {depth}
+        })?);
+
+        // culled like the materials, so the nearest visible face wins
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(depth_config.depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+                .build(device)?,
+        );
+
+        Ok(ObjectIdRenderer {
+            queue,
+            render_pass,
+            pipeline,
+            depth_config,
+            target: None,
+            pending: None,
+        })
+    }
+
+    fn target(
+        &mut self,
+        dimensions: [u32; 2],
+    ) -> Result<&IdTarget, Box<dyn error::Error + Send + Sync>> {
+        let outdated = match self.target {
+            Some(ref target) => target.dimensions != dimensions,
+            None => true,
+        };
+        if outdated {
+            let device = self.queue.device().clone();
+            let ids = AttachmentImage::with_usage(
+                device.clone(),
+                dimensions,
+                Format::R32Uint,
+                ImageUsage {
+                    transfer_source: true,
+                    ..ImageUsage::none()
+                },
+            )?;
+            let depth = AttachmentImage::transient(device, dimensions, Format::D32Sfloat)?;
+            let framebuffer = Arc::new(
+                Framebuffer::start(self.render_pass.clone())
+                    .add(ids.clone())?
+                    .add(depth)?
+                    .build()?,
+            );
+            self.target = Some(IdTarget {
+                dimensions,
+                ids,
+                framebuffer,
+            });
+        }
+        Ok(self.target.as_ref().unwrap())
+    }
+
+    /// Starts looking for the node at `position`, in pixels of a `dimensions` sized screen,
+    /// through the last of `views` containing it. `picked` gives the result, a pick that is
+    /// still pending is replaced.
+    pub fn pick(
+        &mut self,
+        scene: &SceneGraph,
+        views: &[SceneView],
+        dimensions: [u32; 2],
+        position: [f64; 2],
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let normalized = [
+            (position[0] / dimensions[0] as f64) as f32,
+            (position[1] / dimensions[1] as f64) as f32,
+        ];
+        let view = match views
+            .iter()
+            .rev()
+            .find(|view| view.rect.contains(normalized))
+        {
+            Some(view) => view,
+            None => {
+                self.pending = Some(PendingPick::Missed);
+                return Ok(());
+            }
+        };
+        let position = [
+            (position[0] as u32).min(dimensions[0] - 1),
+            (position[1] as u32).min(dimensions[1] - 1),
+        ];
+
+        let queue = self.queue.clone();
+        let device = queue.device().clone();
+        let pipeline = self.pipeline.clone();
+        let clear_values = vec![
+            ClearValue::Uint([0, 0, 0, 0]),
+            self.depth_config.clear_value().into(),
+        ];
+        let target = self.target(dimensions)?;
+
+        let dynamic_state = DynamicState {
+            viewports: Some(vec![view.rect.to_viewport(dimensions)]),
+            ..DynamicState::none()
+        };
+
+        let mut builder =
+            AutoCommandBufferBuilder::primary_one_time_submit(device.clone(), queue.family())?;
+        builder.begin_render_pass(target.framebuffer.clone(), false, clear_values)?;
+        let mut result: Result<(), Box<dyn error::Error + Send + Sync>> = Ok(());
+        scene.visit(&mut |index, node| {
+            let object = match (node.get_object(), &result) {
+                (Some(object), Ok(())) => object,
+                _ => return,
+            };
+            // what is behind a transparent object shows through, so that is what gets picked
+            if object.get_material().blend_mode().is_transparent() {
+                return;
+            }
+            let mesh = object.get_mesh();
+            let parameters = vs::ty::object_parameters {
+                world: object.get_transform().into(),
+                object_id: index as u32 + 1,
+            };
+            let drawn = if mesh.is_indexed() {
+                builder
+                    .draw_indexed(
+                        pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        mesh.index_buffer(),
+                        view.view_set.clone(),
+                        parameters,
+                    )
+                    .map(|_| ())
+                    .map_err(|e| e.into())
+            } else {
+                builder
+                    .draw(
+                        pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        view.view_set.clone(),
+                        parameters,
+                    )
+                    .map(|_| ())
+                    .map_err(|e| e.into())
+            };
+            result = drawn;
+        });
+        result?;
+        builder.end_render_pass()?;
+
+        let pixel = CpuAccessibleBuffer::from_iter(
+            device,
+            BufferUsage::transfer_destination(),
+            false,
+            std::iter::once(0u32),
+        )?;
+        builder.copy_image_to_buffer_dimensions(
+            target.ids.clone(),
+            pixel.clone(),
+            [position[0], position[1], 0],
+            [1, 1, 1],
+            0,
+            1,
+            0,
+        )?;
+
+        let future = builder
+            .build()?
+            .execute(queue)?
+            .then_signal_fence_and_flush()?;
+        self.pending = Some(PendingPick::Drawing {
+            future: Box::new(future),
+            pixel,
+        });
+        Ok(())
+    }
+
+    /// The result of the last `pick` once it is known, the node index or `None` if there
+    /// was nothing at the position. Each result is given once.
+    pub fn picked(&mut self) -> Result<Option<Option<usize>>, Box<dyn error::Error + Send + Sync>> {
+        let id = match self.pending {
+            None => return Ok(None),
+            Some(PendingPick::Missed) => None,
+            Some(PendingPick::Drawing {
+                ref mut future,
+                ref pixel,
+            }) => {
+                // unlocks the pixel once the fence is signaled, without blocking
+                future.cleanup_finished();
+                match pixel.read() {
+                    Ok(pixel) => pixel[0].checked_sub(1).map(|index| index as usize),
+                    Err(ReadLockError::GpuWriteLocked) => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        };
+        self.pending = None;
+        Ok(Some(id))
+    }
+}
@@ -0,0 +1,248 @@
+use std::cmp::Ordering;
+use std::error;
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBuffer, DynamicState};
+use vulkano::descriptor::DescriptorSet;
+use vulkano::device::Queue;
+
+use nalgebra_glm as glm;
+
+use crate::drawable::Drawable;
+use crate::particles::ParticleEmitter;
+use crate::render_target::RenderTarget;
+use crate::scene::{SceneGraph, SceneObject};
+
+/// Identifies a value behind an `Arc` by its address, so that draws sharing
+/// the same pipeline or material compare equal
+fn arc_key<T: ?Sized>(arc: &Arc<T>) -> usize {
+    Arc::as_ptr(arc) as *const () as usize
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SortKey {
+    pub pipeline: usize,
+    pub material: usize,
+    /// Squared distance from the view position to the object's origin
+    pub depth: f32,
+}
+
+impl SortKey {
+    fn compare_depth(&self, other: &SortKey) -> Ordering {
+        self.depth
+            .partial_cmp(&other.depth)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    fn compare_state(&self, other: &SortKey) -> Ordering {
+        self.pipeline
+            .cmp(&other.pipeline)
+            .then(self.material.cmp(&other.material))
+    }
+
+    /// Groups by pipeline, then material, then draws front to back
+    pub fn compare_opaque(&self, other: &SortKey) -> Ordering {
+        self.compare_state(other)
+            .then_with(|| self.compare_depth(other))
+    }
+
+    /// Draws back to front, only grouping draws at the same depth
+    pub fn compare_transparent(&self, other: &SortKey) -> Ordering {
+        other
+            .compare_depth(self)
+            .then_with(|| self.compare_state(other))
+    }
+}
+
+pub struct DrawItem<'a> {
+    pub object: &'a SceneObject,
+    pub key: SortKey,
+}
+
+pub struct RenderQueue<'a> {
+    view_position: glm::Vec3,
+    opaque: Vec<DrawItem<'a>>,
+    transparent: Vec<DrawItem<'a>>,
+    emitters: Vec<&'a ParticleEmitter>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new(scene: &'a SceneGraph, view_position: glm::Vec3) -> Self {
+        let mut queue = RenderQueue {
+            view_position,
+            opaque: vec![],
+            transparent: vec![],
+            emitters: vec![],
+        };
+        scene.enqueue(&mut queue);
+        queue.sort();
+        queue
+    }
+
+    pub fn push(&mut self, object: &'a SceneObject) {
+        let transform = object.get_transform();
+        let position = glm::vec3(transform[(0, 3)], transform[(1, 3)], transform[(2, 3)]);
+        let material = object.get_material();
+        let key = SortKey {
+            pipeline: arc_key(&material.pipeline()),
+            material: arc_key(&material),
+            depth: glm::distance2(&self.view_position, &position),
+        };
+
+        let item = DrawItem { object, key };
+        if material.blend_mode().is_transparent() {
+            self.transparent.push(item);
+        } else {
+            self.opaque.push(item);
+        }
+    }
+
+    pub fn push_emitter(&mut self, emitter: &'a ParticleEmitter) {
+        self.emitters.push(emitter);
+    }
+
+    /// Drops every item whose material reads from `target`, so that rendering
+    /// into a target never samples it, e.g. a mirror doesn't render itself
+    pub fn exclude_sampling(&mut self, target: &RenderTarget) {
+        self.opaque
+            .retain(|item| !item.object.get_material().samples(target));
+        self.transparent
+            .retain(|item| !item.object.get_material().samples(target));
+    }
+
+    pub fn sort(&mut self) {
+        self.opaque.sort_by(|a, b| a.key.compare_opaque(&b.key));
+        self.transparent
+            .sort_by(|a, b| a.key.compare_transparent(&b.key));
+    }
+
+    pub fn opaque(&self) -> &[DrawItem<'a>] {
+        &self.opaque[..]
+    }
+
+    pub fn transparent(&self) -> &[DrawItem<'a>] {
+        &self.transparent[..]
+    }
+
+    /// Particles are drawn after all items
+    pub fn emitters(&self) -> &[&'a ParticleEmitter] {
+        &self.emitters[..]
+    }
+
+    /// All items in submission order: opaque first, then transparent
+    pub fn iter(&self) -> impl Iterator<Item = &DrawItem<'a>> {
+        self.opaque.iter().chain(self.transparent.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        let objects: usize = self
+            .iter()
+            .map(|item| item.object.get_mesh().triangle_count())
+            .sum();
+        let particles: usize = self
+            .emitters
+            .iter()
+            .map(|emitter| emitter.triangle_count())
+            .sum();
+        objects + particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn draw(
+        &self,
+        queue: Arc<Queue>,
+        dynamic_state: &DynamicState,
+        view_set: Arc<dyn DescriptorSet + Send + Sync>,
+        lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
+    ) -> Result<Vec<AutoCommandBuffer>, Box<dyn error::Error + Send + Sync>> {
+        self.iter()
+            .map(|item| item.object as &dyn Drawable)
+            .chain(
+                self.emitters
+                    .iter()
+                    .map(|emitter| *emitter as &dyn Drawable),
+            )
+            .map(|drawable| {
+                drawable.draw(
+                    queue.clone(),
+                    dynamic_state,
+                    view_set.clone(),
+                    lighting_set.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(pipeline: usize, material: usize, depth: f32) -> SortKey {
+        SortKey {
+            pipeline,
+            material,
+            depth,
+        }
+    }
+
+    fn sorted(mut keys: Vec<SortKey>, compare: fn(&SortKey, &SortKey) -> Ordering) -> Vec<SortKey> {
+        keys.sort_by(compare);
+        keys
+    }
+
+    #[test]
+    fn opaque_groups_by_pipeline_then_material() {
+        let keys = vec![
+            key(2, 1, 1.0),
+            key(1, 2, 2.0),
+            key(2, 2, 3.0),
+            key(1, 1, 4.0),
+            key(1, 2, 5.0),
+        ];
+        assert_eq!(
+            sorted(keys, SortKey::compare_opaque),
+            vec![
+                key(1, 1, 4.0),
+                key(1, 2, 2.0),
+                key(1, 2, 5.0),
+                key(2, 1, 1.0),
+                key(2, 2, 3.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn opaque_draws_front_to_back_within_a_group() {
+        let keys = vec![key(1, 1, 9.0), key(1, 1, 1.0), key(1, 1, 4.0)];
+        assert_eq!(
+            sorted(keys, SortKey::compare_opaque),
+            vec![key(1, 1, 1.0), key(1, 1, 4.0), key(1, 1, 9.0)]
+        );
+    }
+
+    #[test]
+    fn transparent_draws_back_to_front_across_states() {
+        let keys = vec![key(1, 1, 1.0), key(2, 2, 9.0), key(1, 2, 4.0)];
+        assert_eq!(
+            sorted(keys, SortKey::compare_transparent),
+            vec![key(2, 2, 9.0), key(1, 2, 4.0), key(1, 1, 1.0)]
+        );
+    }
+
+    #[test]
+    fn transparent_groups_only_at_the_same_depth() {
+        let keys = vec![key(2, 1, 4.0), key(1, 1, 4.0), key(1, 1, 1.0)];
+        assert_eq!(
+            sorted(keys, SortKey::compare_transparent),
+            vec![key(1, 1, 4.0), key(2, 1, 4.0), key(1, 1, 1.0)]
+        );
+    }
+}
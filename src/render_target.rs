@@ -0,0 +1,103 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::framebuffer::{
+    Framebuffer, FramebufferAbstract, RenderPassAbstract, RenderPassCreationError,
+};
+use vulkano::image::{AttachmentImage, ImageUsage};
+use vulkano::pipeline::viewport::Viewport;
+
+/// The single pass render targets are drawn with, clearing color and depth
+pub fn scene_render_pass(
+    device: Arc<Device>,
+    color_format: Format,
+) -> Result<Arc<dyn RenderPassAbstract + Send + Sync>, RenderPassCreationError> {
+    Ok(Arc::new(vulkano::single_pass_renderpass!(device,
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: color_format,
+                samples: 1,
+            },
+            depth: {
+                load: Clear,
+                store: DontCare,
+                format: Format::D32Sfloat,
+                samples: 1,
+            }
+        },
+    pass: {
+        color: [color],
+        depth_stencil: {depth}
+    })?))
+}
+
+/// An offscreen color and depth target that can be sampled or copied after rendering
+pub struct RenderTarget {
+    dimensions: [u32; 2],
+    color: Arc<AttachmentImage>,
+    depth: Arc<AttachmentImage>,
+    framebuffer: Arc<dyn FramebufferAbstract + Send + Sync>,
+}
+
+impl RenderTarget {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+        dimensions: [u32; 2],
+        color_format: Format,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let color = AttachmentImage::with_usage(
+            device.clone(),
+            dimensions,
+            color_format,
+            ImageUsage {
+                sampled: true,
+                transfer_source: true,
+                ..ImageUsage::none()
+            },
+        )?;
+        let depth = AttachmentImage::transient(device, dimensions, Format::D32Sfloat)?;
+
+        let framebuffer = Arc::new(
+            Framebuffer::start(render_pass)
+                .add(color.clone())?
+                .add(depth.clone())?
+                .build()?,
+        );
+
+        Ok(RenderTarget {
+            dimensions,
+            color,
+            depth,
+            framebuffer,
+        })
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    pub fn color(&self) -> Arc<AttachmentImage> {
+        self.color.clone()
+    }
+
+    pub fn depth(&self) -> Arc<AttachmentImage> {
+        self.depth.clone()
+    }
+
+    pub fn framebuffer(&self) -> Arc<dyn FramebufferAbstract + Send + Sync> {
+        self.framebuffer.clone()
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [self.dimensions[0] as f32, self.dimensions[1] as f32],
+            depth_range: 0.0..1.0,
+        }
+    }
+}
@@ -2,14 +2,14 @@ use std::error;
 use std::fmt;
 use std::sync::Arc;
 
-use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::command_buffer::{AutoCommandBuffer, AutoCommandBufferBuilder, DynamicState};
 use vulkano::descriptor::DescriptorSet;
-use vulkano::device::Device;
-use vulkano::format::Format;
+use vulkano::device::Queue;
+use vulkano::format::ClearValue;
 use vulkano::framebuffer::{
     Framebuffer, FramebufferAbstract, RenderPassAbstract, RenderPassCreationError,
 };
-use vulkano::image::{AttachmentImage, ImageUsage, SwapchainImage};
+use vulkano::image::{ImageUsage, SwapchainImage};
 use vulkano::pipeline::viewport::Viewport;
 use vulkano::swapchain;
 use vulkano::swapchain::{
@@ -22,12 +22,29 @@ use vulkano::sync::{FlushError, GpuFuture};
 use winit::window::Window;
 
 use crate::context::RenderContext;
+use crate::debug::{DebugDraw, DebugRenderer};
+use crate::depth::DepthConfig;
+use crate::fractal_explorer::FractalExplorer;
+use crate::gui::{GuiOutput, GuiRenderer};
+use crate::material::fractal::FractalParameters;
+use crate::material::pipelines::MaterialPipelines;
+use crate::outline::{OutlineRenderer, OutlineStyle};
+use crate::particles::ParticleEmitter;
+use crate::picking::ObjectIdRenderer;
+use crate::render_queue::RenderQueue;
+use crate::render_target::{scene_render_pass, RenderTarget};
 use crate::scene::SceneGraph;
+use crate::ssao::{AmbientOcclusion, SsaoParameters};
+use crate::text::{TextDraw, TextRenderer};
+use crate::uniforms::SceneLayouts;
+use crate::upscale::{UpscaleFilter, Upscaler};
+use crate::view::{ViewportClear, ViewportRect};
+
+pub(crate) const CLEAR_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 1.0];
 
 fn window_size_dependent_setup(
-    device: Arc<Device>,
     images: &[Arc<SwapchainImage<Window>>],
-    render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    present_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
     dynamic_state: &mut DynamicState,
 ) -> Vec<Arc<dyn FramebufferAbstract + Send + Sync>> {
     let dimensions = images[0].dimensions();
@@ -40,17 +57,13 @@ fn window_size_dependent_setup(
 
     dynamic_state.viewports = Some(vec![viewport]);
 
-    let depth_buffer = AttachmentImage::transient(device, dimensions, Format::D32Sfloat).unwrap();
-
     images
         .iter()
         .map(|image| {
             Arc::new(
-                Framebuffer::start(render_pass.clone())
+                Framebuffer::start(present_pass.clone())
                     .add(image.clone())
                     .unwrap()
-                    .add(depth_buffer.clone())
-                    .unwrap()
                     .build()
                     .unwrap(),
             ) as Arc<dyn FramebufferAbstract + Send + Sync>
@@ -58,6 +71,118 @@ fn window_size_dependent_setup(
         .collect::<Vec<_>>()
 }
 
+fn scaled_dimensions(dimensions: [u32; 2], scale: f32) -> [u32; 2] {
+    [
+        ((dimensions[0] as f32 * scale).round() as u32).max(1),
+        ((dimensions[1] as f32 * scale).round() as u32).max(1),
+    ]
+}
+
+/// Simulates each emitter once, however many views it is seen in
+pub(crate) fn dispatch_emitters<'a, 'b: 'a>(
+    builder: &mut AutoCommandBufferBuilder,
+    views: impl Iterator<Item = &'a SceneView<'b>>,
+) {
+    let mut emitters: Vec<&ParticleEmitter> = Vec::new();
+    for view in views {
+        for emitter in view.render_queue.emitters() {
+            if !emitters.iter().any(|e| std::ptr::eq(*e, *emitter)) {
+                emitters.push(emitter);
+            }
+        }
+    }
+    for emitter in emitters {
+        emitter
+            .dispatch(builder)
+            .expect("Could not add particle simulation to cmd buffer");
+    }
+}
+
+/// Draws each view in order, later views are drawn over earlier ones where they overlap
+pub(crate) fn draw_views(
+    queue: Arc<Queue>,
+    dimensions: [u32; 2],
+    views: &[&SceneView],
+    viewport_clear: &ViewportClear,
+    clear_color: [f32; 4],
+    depth_config: DepthConfig,
+    debug: Option<(&DebugRenderer, &DebugDraw)>,
+) -> Vec<AutoCommandBuffer> {
+    let mut sub_command_buffers = Vec::new();
+    for (i, view) in views.iter().enumerate() {
+        let dynamic_state = DynamicState {
+            viewports: Some(vec![view.rect.to_viewport(dimensions)]),
+            ..DynamicState::none()
+        };
+
+        // the render pass already cleared everything for the first view
+        if i > 0 {
+            sub_command_buffers.push(
+                viewport_clear
+                    .draw(
+                        queue.clone(),
+                        &dynamic_state,
+                        clear_color,
+                        depth_config.clear_value(),
+                    )
+                    .expect("Could not add viewport clear to cmd buffer"),
+            );
+        }
+
+        sub_command_buffers.append(
+            &mut view
+                .render_queue
+                .draw(
+                    queue.clone(),
+                    &dynamic_state,
+                    view.view_set.clone(),
+                    view.lighting_set.clone(),
+                )
+                .expect("Could not add scene draw to cmd buffer"),
+        );
+
+        if let Some((debug_renderer, debug)) = debug.filter(|(_, debug)| !debug.is_empty()) {
+            sub_command_buffers.append(
+                &mut debug_renderer
+                    .draw(queue.clone(), &dynamic_state, view.view_set.clone(), debug)
+                    .expect("Could not add debug draw to cmd buffer"),
+            );
+        }
+    }
+    sub_command_buffers
+}
+
+/// One camera's part of a frame: what to draw, with which uniforms, and where on screen
+pub struct SceneView<'a> {
+    pub rect: ViewportRect,
+    pub render_queue: RenderQueue<'a>,
+    pub view_set: Arc<dyn DescriptorSet + Send + Sync>,
+    pub lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+/// A view rendered into an offscreen target before the main views, so that
+/// materials drawn in the main views can sample it
+pub struct OffscreenView<'a> {
+    target: Arc<RenderTarget>,
+    view: SceneView<'a>,
+}
+
+impl<'a> OffscreenView<'a> {
+    /// Leaves out objects sampling `target` from the view, so nothing reads the target while
+    /// it is rendered. Targets sampled inside other offscreen views show the previous frame.
+    pub fn new(target: Arc<RenderTarget>, mut view: SceneView<'a>) -> Self {
+        view.render_queue.exclude_sampling(&target);
+        OffscreenView { target, view }
+    }
+}
+
+/// Scene work in a frame, over every view including offscreen ones
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    pub triangles: usize,
+}
+
 #[derive(Debug)]
 pub enum RendererCreationError {
     SwapchainError(SwapchainCreationError),
@@ -99,15 +224,38 @@ pub struct Renderer {
     surface: Arc<Surface<Window>>,
     swapchain: Arc<Swapchain<Window>>,
     render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    present_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
     framebuffers: Vec<Arc<dyn FramebufferAbstract + Send + Sync>>,
     dynamic_state: DynamicState,
+    scene_target: RenderTarget,
+    scene_set: Arc<dyn DescriptorSet + Send + Sync>,
+    viewport_clear: ViewportClear,
+    debug: DebugDraw,
+    debug_renderer: DebugRenderer,
+    text: TextDraw,
+    text_renderer: TextRenderer,
+    gui_renderer: GuiRenderer,
+    object_ids: ObjectIdRenderer,
+    outline_renderer: OutlineRenderer,
+    ambient_occlusion: AmbientOcclusion,
+    scene_layouts: Arc<SceneLayouts>,
+    material_pipelines: Arc<MaterialPipelines>,
+    stats: RenderStats,
+    upscaler: Upscaler,
+    fractal_explorer: FractalExplorer,
+    fractal: Option<FractalParameters>,
+    depth_config: DepthConfig,
+    clear_color: [f32; 4],
+    render_scale: f32,
     should_recreate_swapchain: bool,
+    should_recreate_scene_target: bool,
 }
 
 impl Renderer {
     pub fn new(
         context: Arc<RenderContext>,
         surface: Arc<Surface<Window>>,
+        depth_config: DepthConfig,
     ) -> Result<Self, RendererCreationError> {
         let caps = surface
             .capabilities(context.physical_device())
@@ -134,54 +282,252 @@ impl Renderer {
             ColorSpace::SrgbNonLinear,
         )?;
 
-        let render_pass = Arc::new(vulkano::single_pass_renderpass!(context.device(),
+        let render_pass = scene_render_pass(context.device(), swapchain.format())?;
+
+        let present_pass = Arc::new(vulkano::single_pass_renderpass!(context.device(),
             attachments: {
                 color: {
-                    load: Clear,
+                    load: DontCare,
                     store: Store,
                     format: swapchain.format(),
                     samples: 1,
-                },
-                depth: {
-                    load: Clear,
-                    store: DontCare,
-                    format: Format::D32Sfloat,
-                    samples: 1,
                 }
             },
         pass: {
             color: [color],
-            depth_stencil: {depth}
+            depth_stencil: {}
         })?);
 
         let mut dynamic_state = DynamicState::none();
 
-        let framebuffers = window_size_dependent_setup(
+        let framebuffers =
+            window_size_dependent_setup(&images, present_pass.clone(), &mut dynamic_state);
+
+        let scene_target = RenderTarget::new(
             context.device(),
-            &images,
             render_pass.clone(),
-            &mut dynamic_state,
+            dimensions,
+            swapchain.format(),
+        )
+        .expect("Could not create scene render target");
+
+        let viewport_clear = ViewportClear::new(context.device(), render_pass.clone())
+            .expect("Could not create viewport clear pipeline");
+
+        let debug_renderer =
+            DebugRenderer::new(context.device(), render_pass.clone(), depth_config)
+                .expect("Could not create debug line pipelines");
+
+        let text_renderer = TextRenderer::new(context.queue(), present_pass.clone())
+            .expect("Could not create text pipeline");
+
+        let gui_renderer = GuiRenderer::new(context.queue(), present_pass.clone())
+            .expect("Could not create GUI pipeline");
+
+        let object_ids = ObjectIdRenderer::new(context.queue(), depth_config)
+            .expect("Could not create object ID pipeline");
+
+        let outline_renderer = OutlineRenderer::new(context.queue(), present_pass.clone())
+            .expect("Could not create outline pipelines");
+
+        let ambient_occlusion = AmbientOcclusion::new(context.queue(), dimensions, depth_config)
+            .expect("Could not create ambient occlusion pipelines");
+
+        let scene_layouts = Arc::new(
+            SceneLayouts::new(context.device()).expect("Could not create scene set layouts"),
         );
 
+        let upscaler = Upscaler::new(
+            context.device(),
+            present_pass.clone(),
+            UpscaleFilter::Bilinear,
+        )
+        .expect("Could not create upscale pipeline");
+        let scene_set = upscaler
+            .source_set(scene_target.color())
+            .expect("Could not create upscale descriptor set");
+        let material_pipelines =
+            MaterialPipelines::new(context.device(), render_pass.clone(), depth_config);
+        let fractal_explorer = FractalExplorer::new(context.device(), present_pass.clone())
+            .expect("Could not create fractal explorer pipelines");
+
         Ok(Renderer {
             context,
             surface,
             swapchain,
             render_pass,
+            present_pass,
             framebuffers,
             dynamic_state,
+            scene_target,
+            scene_set,
+            viewport_clear,
+            debug: DebugDraw::default(),
+            debug_renderer,
+            text: TextDraw::default(),
+            text_renderer,
+            gui_renderer,
+            object_ids,
+            outline_renderer,
+            ambient_occlusion,
+            scene_layouts,
+            material_pipelines,
+            stats: RenderStats::default(),
+            upscaler,
+            fractal_explorer,
+            fractal: None,
+            depth_config,
+            clear_color: CLEAR_COLOR,
+            render_scale: 1.0,
             should_recreate_swapchain: false,
+            should_recreate_scene_target: false,
         })
     }
 
+    /// The render pass the scene is drawn in, which materials build their pipelines against
     pub fn render_pass(&self) -> Arc<dyn RenderPassAbstract + Send + Sync> {
         self.render_pass.clone()
     }
 
+    /// Materials must build their depth state from this to match the depth buffer's clear value
+    pub fn depth_config(&self) -> DepthConfig {
+        self.depth_config
+    }
+
+    /// Where materials get their pipelines, built against `render_pass` and `depth_config`
+    pub fn material_pipelines(&self) -> Arc<MaterialPipelines> {
+        self.material_pipelines.clone()
+    }
+
+    /// Creates a fixed size target compatible with `render_pass`, to be rendered into with an `OffscreenView`
+    pub fn create_render_target(
+        &self,
+        dimensions: [u32; 2],
+    ) -> Result<Arc<RenderTarget>, Box<dyn error::Error + Send + Sync>> {
+        Ok(Arc::new(RenderTarget::new(
+            self.context.device(),
+            self.render_pass.clone(),
+            dimensions,
+            self.swapchain.format(),
+        )?))
+    }
+
+    /// Lines added here are drawn over the main views of the next frame
+    pub fn debug(&mut self) -> &mut DebugDraw {
+        &mut self.debug
+    }
+
+    /// Text added here is drawn over the next frame, in window pixels
+    pub fn text(&mut self) -> &mut TextDraw {
+        &mut self.text
+    }
+
+    /// Drawn over the next frame, on top of the text
+    pub fn set_gui(&mut self, output: GuiOutput) {
+        self.gui_renderer.set_output(output);
+    }
+
+    /// Starts looking for the node drawn at `position` in window pixels, looking through the
+    /// last of `views` containing it, `picked` gives the result
+    pub fn pick(&mut self, scene: &SceneGraph, views: &[SceneView], position: [f64; 2]) {
+        let dimensions = self.swapchain.dimensions();
+        self.object_ids
+            .pick(scene, views, dimensions, position)
+            .expect("Could not pick object")
+    }
+
+    /// Once the GPU has drawn the last `pick`, the index of the node it found as given by
+    /// `SceneGraph::visit`, or `None` if there was nothing there
+    pub fn picked(&mut self) -> Option<Option<usize>> {
+        self.object_ids
+            .picked()
+            .expect("Could not read picked object")
+    }
+
+    pub fn outline_style(&self) -> OutlineStyle {
+        self.outline_renderer.style()
+    }
+
+    /// How objects highlighted with `SceneObject::set_highlighted` are outlined
+    pub fn set_outline_style(&mut self, style: OutlineStyle) {
+        self.outline_renderer.set_style(style);
+    }
+
+    /// The layouts the view and lighting sets of every `SceneView` are built against
+    pub fn scene_layouts(&self) -> Arc<SceneLayouts> {
+        self.scene_layouts.clone()
+    }
+
+    /// Lit materials in the main views must sample `AmbientOcclusion::occlusion` in their
+    /// lighting sets, and `AmbientOcclusion::no_occlusion` in offscreen views
+    pub fn ambient_occlusion(&self) -> &AmbientOcclusion {
+        &self.ambient_occlusion
+    }
+
+    pub fn ssao_parameters(&self) -> SsaoParameters {
+        self.ambient_occlusion.parameters()
+    }
+
+    pub fn set_ssao_parameters(&mut self, parameters: SsaoParameters) {
+        self.ambient_occlusion.set_parameters(parameters);
+    }
+
+    /// Counters from the last rendered frame
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
     pub fn resized(&mut self) {
         self.should_recreate_swapchain = true;
     }
 
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Renders the scene at `scale` times the window size, it is upscaled to the window afterwards
+    pub fn set_render_scale(&mut self, scale: f32) {
+        if scale != self.render_scale {
+            self.render_scale = scale;
+            self.should_recreate_scene_target = true;
+        }
+    }
+
+    pub fn render_dimensions(&self) -> [u32; 2] {
+        self.scene_target.dimensions()
+    }
+
+    /// The background of the main views, fog should fade into it
+    pub fn clear_color(&self) -> [f32; 4] {
+        self.clear_color
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: [f32; 4]) {
+        self.clear_color = clear_color;
+    }
+
+    pub fn fractal_explorer(&self) -> Option<FractalParameters> {
+        self.fractal
+    }
+
+    /// Shows a fractal over the whole window in place of the scene, until set back to `None`
+    pub fn set_fractal_explorer(&mut self, fractal: Option<FractalParameters>) {
+        self.fractal = fractal;
+    }
+
+    /// Whether the fractal explorer computes in double precision, which takes `shader_f3264`
+    pub fn fractal_explorer_is_double_precision(&self) -> bool {
+        self.fractal_explorer.is_double_precision()
+    }
+
+    pub fn upscale_filter(&self) -> UpscaleFilter {
+        self.upscaler.filter()
+    }
+
+    pub fn set_upscale_filter(&mut self, filter: UpscaleFilter) {
+        self.upscaler.set_filter(filter);
+    }
+
     fn recreate_swapchain_if_needed(&mut self) {
         if self.should_recreate_swapchain {
             let dimensions: [u32; 2] = self.surface.window().inner_size().into();
@@ -196,23 +542,47 @@ impl Renderer {
 
             self.swapchain = new_swapchain;
             self.framebuffers = window_size_dependent_setup(
-                self.context.device(),
                 &new_images,
-                self.render_pass.clone(),
+                self.present_pass.clone(),
                 &mut self.dynamic_state,
             );
             self.should_recreate_swapchain = false;
+            self.should_recreate_scene_target = true;
+        }
+    }
+
+    fn recreate_scene_target_if_needed(&mut self) {
+        if self.should_recreate_scene_target {
+            let dimensions = scaled_dimensions(self.swapchain.dimensions(), self.render_scale);
+            if dimensions != self.scene_target.dimensions() {
+                self.scene_target = RenderTarget::new(
+                    self.context.device(),
+                    self.render_pass.clone(),
+                    dimensions,
+                    self.swapchain.format(),
+                )
+                .expect("Could not recreate scene render target");
+                self.scene_set = self
+                    .upscaler
+                    .source_set(self.scene_target.color())
+                    .expect("Could not create upscale descriptor set");
+                self.ambient_occlusion
+                    .resize(dimensions)
+                    .expect("Could not recreate ambient occlusion targets");
+            }
+            self.should_recreate_scene_target = false;
         }
     }
 
+    /// Renders the offscreen views in order, then the main views to the window
     pub fn render(
         &mut self,
-        scene: &SceneGraph,
-        camera_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
-        lighting_descriptors: Arc<dyn DescriptorSet + Send + Sync>,
+        offscreen_views: &[OffscreenView],
+        views: &[SceneView],
         mut previous_frame_end: Option<Box<dyn GpuFuture>>,
     ) -> Option<Box<dyn GpuFuture>> {
         self.recreate_swapchain_if_needed();
+        self.recreate_scene_target_if_needed();
 
         let queue = self.context.queue();
 
@@ -221,6 +591,8 @@ impl Renderer {
                 Ok(r) => r,
                 Err(AcquireError::OutOfDate) => {
                     self.should_recreate_swapchain = true;
+                    self.debug.clear();
+                    self.text.clear();
                     return previous_frame_end;
                 }
                 Err(e) => panic!("Failed to acquire next image: {:?}", e),
@@ -230,7 +602,10 @@ impl Renderer {
             self.should_recreate_swapchain = true;
         }
 
-        let clear_values = vec![[0.1, 0.1, 0.1, 1.0].into(), 1f32.into()];
+        let clear_values = vec![
+            self.clear_color.into(),
+            self.depth_config.clear_value().into(),
+        ];
 
         let mut builder = AutoCommandBufferBuilder::primary_one_time_submit(
             self.context.device(),
@@ -238,18 +613,72 @@ impl Renderer {
         )
         .unwrap();
 
+        dispatch_emitters(
+            &mut builder,
+            offscreen_views
+                .iter()
+                .map(|offscreen_view| &offscreen_view.view)
+                .chain(views.iter()),
+        );
+
+        self.gui_renderer
+            .upload(&mut builder)
+            .expect("Could not add GUI texture upload to cmd buffer");
+
+        let mut stats = RenderStats::default();
+        for offscreen_view in offscreen_views {
+            builder
+                .begin_render_pass(
+                    offscreen_view.target.framebuffer(),
+                    true,
+                    clear_values.clone(),
+                )
+                .unwrap();
+            let sub_command_buffers = draw_views(
+                queue.clone(),
+                offscreen_view.target.dimensions(),
+                &[&offscreen_view.view],
+                &self.viewport_clear,
+                self.clear_color,
+                self.depth_config,
+                None,
+            );
+            stats.draw_calls += sub_command_buffers.len();
+            stats.triangles += offscreen_view.view.render_queue.triangle_count();
+            // executing a secondary command buffer is unsafe for now
+            unsafe {
+                builder
+                    .execute_commands_from_vec(sub_command_buffers)
+                    .unwrap();
+            }
+            builder.end_render_pass().unwrap();
+        }
+
+        let views: Vec<&SceneView> = views.iter().collect();
+        stats.draw_calls += self
+            .ambient_occlusion
+            .draw(&mut builder, &views)
+            .expect("Could not add ambient occlusion to cmd buffer");
+
         builder
-            .begin_render_pass(self.framebuffers[image_num].clone(), false, clear_values)
+            .begin_render_pass(self.scene_target.framebuffer(), true, clear_values)
             .unwrap();
 
-        let sub_command_buffers = scene
-            .draw(
-                queue.clone(),
-                &self.dynamic_state,
-                camera_descriptors,
-                lighting_descriptors,
-            )
-            .expect("Could not add scene draw to cmd buffer");
+        let sub_command_buffers = draw_views(
+            queue.clone(),
+            self.scene_target.dimensions(),
+            &views,
+            &self.viewport_clear,
+            self.clear_color,
+            self.depth_config,
+            Some((&self.debug_renderer, &self.debug)),
+        );
+        self.debug.clear();
+        stats.draw_calls += sub_command_buffers.len();
+        stats.triangles += views
+            .iter()
+            .map(|view| view.render_queue.triangle_count())
+            .sum::<usize>();
 
         // executing a secondary command buffer is unsafe for now
         unsafe {
@@ -260,6 +689,49 @@ impl Renderer {
 
         builder.end_render_pass().unwrap();
 
+        // drawn at window resolution, so outlines stay sharp at any render scale
+        let outlined = self
+            .outline_renderer
+            .draw_mask(&mut builder, self.swapchain.dimensions(), &views)
+            .expect("Could not add outline mask to cmd buffer");
+
+        builder
+            .begin_render_pass(
+                self.framebuffers[image_num].clone(),
+                false,
+                vec![ClearValue::None],
+            )
+            .unwrap();
+        let [width, height] = self.swapchain.dimensions();
+        if let Some(ref fractal) = self.fractal {
+            self.fractal_explorer
+                .draw(&mut builder, &self.dynamic_state, fractal, [width, height])
+                .expect("Could not add fractal draw to cmd buffer");
+        } else {
+            self.upscaler
+                .draw(&mut builder, &self.dynamic_state, self.scene_set.clone())
+                .expect("Could not add upscale draw to cmd buffer");
+            if outlined {
+                self.outline_renderer
+                    .draw(&mut builder, &self.dynamic_state)
+                    .expect("Could not add outline draw to cmd buffer");
+            }
+        }
+        self.text_renderer
+            .draw(
+                &mut builder,
+                &self.dynamic_state,
+                [width as f32, height as f32],
+                &self.text,
+            )
+            .expect("Could not add text draw to cmd buffer");
+        self.text.clear();
+        self.gui_renderer
+            .draw(&mut builder, &self.dynamic_state, [width, height])
+            .expect("Could not add GUI draw to cmd buffer");
+        builder.end_render_pass().unwrap();
+        self.stats = stats;
+
         let command_buffer = builder.build().unwrap();
 
         let future = previous_frame_end
@@ -0,0 +1,152 @@
+use crate::utility::clamp;
+
+// Weight of the newest frame in the running frame time average
+const SMOOTHING: f32 = 0.1;
+// Frames to wait after a change before adjusting again
+const ADJUST_INTERVAL: u32 = 30;
+const SCALE_STEP: f32 = 0.05;
+// Frame times within this fraction of the target leave the scale alone
+const TOLERANCE: f32 = 0.1;
+
+/// Picks a render scale that keeps the average frame time near a target
+#[derive(Clone, Debug)]
+pub struct ResolutionController {
+    target_frame_time: f32,
+    min_scale: f32,
+    max_scale: f32,
+    scale: f32,
+    average_frame_time: f32,
+    frames_until_adjust: u32,
+}
+
+impl ResolutionController {
+    pub fn new(target_frame_time: f32, min_scale: f32, max_scale: f32) -> Self {
+        ResolutionController {
+            target_frame_time,
+            min_scale,
+            max_scale,
+            scale: max_scale,
+            average_frame_time: target_frame_time,
+            frames_until_adjust: ADJUST_INTERVAL,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn average_frame_time(&self) -> f32 {
+        self.average_frame_time
+    }
+
+    pub fn target_frame_time(&self) -> f32 {
+        self.target_frame_time
+    }
+
+    pub fn set_target_frame_time(&mut self, target_frame_time: f32) {
+        self.target_frame_time = target_frame_time;
+    }
+
+    pub fn set_bounds(&mut self, min_scale: f32, max_scale: f32) {
+        self.min_scale = min_scale;
+        self.max_scale = max_scale;
+        self.scale = clamp(self.scale, min_scale, max_scale);
+    }
+
+    pub fn update(&mut self, frame_time: f32) -> f32 {
+        self.average_frame_time += (frame_time - self.average_frame_time) * SMOOTHING;
+
+        if self.frames_until_adjust > 0 {
+            self.frames_until_adjust -= 1;
+            return self.scale;
+        }
+
+        let ratio = self.target_frame_time / self.average_frame_time;
+        if (ratio - 1.0).abs() > TOLERANCE {
+            // Frame time grows roughly with the pixel count, so with the square of the scale
+            let scale = (self.scale * ratio.sqrt() / SCALE_STEP).round() * SCALE_STEP;
+            let scale = if ratio > 1.0 {
+                scale.max(self.scale + SCALE_STEP)
+            } else {
+                scale.min(self.scale - SCALE_STEP)
+            };
+
+            let scale = clamp(scale, self.min_scale, self.max_scale);
+            if scale != self.scale {
+                self.scale = scale;
+                self.frames_until_adjust = ADJUST_INTERVAL;
+            }
+        }
+
+        self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: f32 = 1.0 / 60.0;
+
+    fn run(controller: &mut ResolutionController, frame_time: f32, frames: u32) -> f32 {
+        for _ in 0..frames {
+            controller.update(frame_time);
+        }
+        controller.scale()
+    }
+
+    #[test]
+    fn scale_drops_when_over_budget() {
+        let mut controller = ResolutionController::new(TARGET, 0.5, 1.0);
+        let scale = run(&mut controller, TARGET * 2.0, 100);
+        assert!(scale < 1.0, "scale stayed at {}", scale);
+    }
+
+    #[test]
+    fn scale_recovers_when_under_budget() {
+        let mut controller = ResolutionController::new(TARGET, 0.5, 1.0);
+        let dropped = run(&mut controller, TARGET * 2.0, 100);
+        let recovered = run(&mut controller, TARGET * 0.5, 300);
+        assert!(
+            recovered > dropped,
+            "scale went from {} to {}",
+            dropped,
+            recovered
+        );
+    }
+
+    /// A controller whose scale has dropped once, away from both of its bounds
+    fn dropped_once() -> ResolutionController {
+        let mut controller = ResolutionController::new(TARGET, 0.2, 1.0);
+        let scale = run(&mut controller, TARGET * 1.3, 40);
+        assert!(scale > 0.2 && scale < 1.0, "scale went to {}", scale);
+        controller
+    }
+
+    #[test]
+    fn scale_holds_within_tolerance() {
+        let mut controller = dropped_once();
+        let scale = controller.scale();
+        assert_eq!(
+            run(&mut controller, TARGET * (1.0 + TOLERANCE / 2.0), 500),
+            scale
+        );
+    }
+
+    #[test]
+    fn scale_changes_beyond_tolerance() {
+        let mut controller = dropped_once();
+        let scale = controller.scale();
+        assert!(run(&mut controller, TARGET * (1.0 + TOLERANCE * 2.0), 500) < scale);
+    }
+
+    #[test]
+    fn scale_stays_within_bounds() {
+        let mut controller = ResolutionController::new(TARGET, 0.5, 0.8);
+        assert_eq!(run(&mut controller, TARGET * 100.0, 1000), 0.5);
+        assert_eq!(run(&mut controller, TARGET * 0.01, 1000), 0.8);
+
+        controller.set_bounds(0.6, 0.7);
+        assert_eq!(controller.scale(), 0.7);
+    }
+}
@@ -1,28 +1,29 @@
-use std::error;
 use std::sync::Arc;
 
-use vulkano::command_buffer::{AutoCommandBuffer, DynamicState};
-use vulkano::descriptor::DescriptorSet;
-use vulkano::device::Queue;
-
 use nalgebra_glm as glm;
 
 use super::SceneObject;
-use crate::drawable::Drawable;
+use crate::material::Material;
+use crate::particles::ParticleEmitter;
+use crate::render_queue::RenderQueue;
 
 pub struct SceneGraph {
+    name: String,
     parent_transform: glm::Mat4,
     world_transform: glm::Mat4,
     object: Option<SceneObject>,
+    emitter: Option<ParticleEmitter>,
     children: Vec<SceneGraph>,
 }
 
 impl Default for SceneGraph {
     fn default() -> Self {
         SceneGraph {
+            name: String::new(),
             parent_transform: glm::identity(),
             world_transform: glm::identity(),
             object: None,
+            emitter: None,
             children: vec![],
         }
     }
@@ -35,13 +36,23 @@ impl SceneGraph {
         children: Vec<SceneGraph>,
     ) -> Self {
         SceneGraph {
+            name: String::new(),
             parent_transform,
             world_transform: parent_transform,
             object,
+            emitter: None,
             children,
         }
     }
 
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name<T: Into<String>>(&mut self, name: T) {
+        self.name = name.into();
+    }
+
     pub fn get_parent_transform(&self) -> glm::Mat4 {
         self.parent_transform
     }
@@ -55,6 +66,11 @@ impl SceneGraph {
         self.update_transform(parents_transform);
     }
 
+    /// The parent transform combined with every ancestor's
+    pub fn get_world_transform(&self) -> glm::Mat4 {
+        self.world_transform
+    }
+
     pub fn get_object(&self) -> &Option<SceneObject> {
         &self.object
     }
@@ -63,6 +79,23 @@ impl SceneGraph {
         self.object = Some(object);
     }
 
+    pub fn get_object_mut(&mut self) -> &mut Option<SceneObject> {
+        &mut self.object
+    }
+
+    pub fn get_emitter(&self) -> &Option<ParticleEmitter> {
+        &self.emitter
+    }
+
+    pub fn get_emitter_mut(&mut self) -> &mut Option<ParticleEmitter> {
+        &mut self.emitter
+    }
+
+    pub fn set_emitter(&mut self, mut emitter: ParticleEmitter) {
+        emitter.set_transform(self.world_transform);
+        self.emitter = Some(emitter);
+    }
+
     pub fn add_child(&mut self, mut child: SceneGraph) {
         child.update_transform(self.world_transform);
         self.children.push(child);
@@ -82,43 +115,119 @@ impl SceneGraph {
         &mut self.children[..]
     }
 
+    /// Calls `f` with every node and its index, parents before their children
+    pub fn visit<F: FnMut(usize, &SceneGraph)>(&self, f: &mut F) {
+        self.visit_from(&mut 0, f);
+    }
+
+    fn visit_from<F: FnMut(usize, &SceneGraph)>(&self, index: &mut usize, f: &mut F) {
+        f(*index, self);
+        *index += 1;
+        for child in &self.children {
+            child.visit_from(index, f);
+        }
+    }
+
+    /// The node `visit` gives `index`
+    pub fn get_node(&self, index: usize) -> Option<&SceneGraph> {
+        self.find_node(&mut index.clone())
+    }
+
+    fn find_node(&self, remaining: &mut usize) -> Option<&SceneGraph> {
+        if *remaining == 0 {
+            return Some(self);
+        }
+        *remaining -= 1;
+        self.children
+            .iter()
+            .find_map(|child| child.find_node(remaining))
+    }
+
+    pub fn get_node_mut(&mut self, index: usize) -> Option<&mut SceneGraph> {
+        self.find_node_mut(&mut index.clone())
+    }
+
+    fn find_node_mut(&mut self, remaining: &mut usize) -> Option<&mut SceneGraph> {
+        if *remaining == 0 {
+            return Some(self);
+        }
+        *remaining -= 1;
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_node_mut(remaining))
+    }
+
+    /// Highlights the objects of the nodes `visit` gives these indices, and no others
+    pub fn set_highlighted(&mut self, nodes: &[usize]) {
+        self.set_highlighted_from(&mut 0, nodes);
+    }
+
+    fn set_highlighted_from(&mut self, index: &mut usize, nodes: &[usize]) {
+        if let Some(ref mut object) = self.object {
+            object.set_highlighted(nodes.contains(index));
+        }
+        *index += 1;
+        for child in &mut self.children {
+            child.set_highlighted_from(index, nodes);
+        }
+    }
+
     fn update_transform(&mut self, parents_transform: glm::Mat4) {
         self.world_transform = parents_transform * self.parent_transform;
         if let Some(ref mut object) = self.object {
             object.set_transform(self.world_transform);
         }
+        if let Some(ref mut emitter) = self.emitter {
+            emitter.set_transform(self.world_transform);
+        }
 
         for child in &mut self.children {
             child.update_transform(self.world_transform);
         }
     }
 
-    pub fn draw(
-        &self,
-        queue: Arc<Queue>,
-        dynamic_state: &DynamicState,
-        view_set: Arc<dyn DescriptorSet + Send + Sync>,
-        lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
-    ) -> Result<Vec<AutoCommandBuffer>, Box<dyn error::Error + Send + Sync>> {
-        let mut vec = Vec::new();
+    pub fn enqueue<'a>(&'a self, queue: &mut RenderQueue<'a>) {
         if let Some(ref object) = self.object {
-            vec.push(object.draw(
-                queue.clone(),
-                dynamic_state,
-                view_set.clone(),
-                lighting_set.clone(),
-            )?);
+            queue.push(object);
+        }
+        if let Some(ref emitter) = self.emitter {
+            queue.push_emitter(emitter);
         }
 
         for child in &self.children {
-            vec.append(&mut child.draw(
-                queue.clone(),
-                dynamic_state,
-                view_set.clone(),
-                lighting_set.clone(),
-            )?);
+            child.enqueue(queue);
         }
+    }
 
-        Ok(vec)
+    /// Advances every emitter in the graph by `delta_time` seconds
+    pub fn update_particles(&mut self, delta_time: f32) {
+        if let Some(ref mut emitter) = self.emitter {
+            emitter.update(delta_time);
+        }
+
+        for child in &mut self.children {
+            child.update_particles(delta_time);
+        }
+    }
+
+    /// Gives every object in the graph using `old` the material `new` instead
+    pub fn replace_material(
+        &mut self,
+        old: &Arc<dyn Material + Send + Sync>,
+        new: Arc<dyn Material + Send + Sync>,
+    ) {
+        if let Some(ref mut object) = self.object {
+            // compare the data pointers only, vtables may be duplicated across codegen units
+            if std::ptr::eq(
+                Arc::as_ptr(&object.get_material()) as *const (),
+                Arc::as_ptr(old) as *const (),
+            ) {
+                object.set_material(new.clone());
+            }
+        }
+
+        for child in &mut self.children {
+            child.replace_material(old, new.clone());
+        }
     }
 }
@@ -1,27 +1,32 @@
 use std::error;
+use std::iter;
 use std::sync::Arc;
 
 use vulkano::buffer::CpuBufferPool;
 use vulkano::command_buffer::{AutoCommandBuffer, AutoCommandBufferBuilder, DynamicState};
 use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
-use vulkano::descriptor::DescriptorSet;
+use vulkano::descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
 use vulkano::device::{Device, Queue};
 use vulkano::pipeline::GraphicsPipelineAbstract;
 
 use nalgebra_glm as glm;
 
 use crate::drawable::Drawable;
-use crate::material::Material;
+use crate::material::{Material, MaterialPass};
 use crate::mesh::Mesh;
 
-// TODO HOW THE HELL DO WE DEAL WITH UNIFORM TYPES
-use crate::material::phong::vs::ty::world_matrix;
+use crate::uniforms::{self, ObjectUniforms};
 
 pub struct SceneObject {
     transform: glm::Mat4,
     material: Arc<dyn Material + Send + Sync>,
     mesh: Arc<dyn Mesh + Send + Sync>,
-    uniform_buffer_pool: CpuBufferPool<world_matrix>,
+    highlighted: bool,
+    uniform_buffer_pool: CpuBufferPool<ObjectUniforms>,
+    layout: Arc<UnsafeDescriptorSetLayout>,
+    /// Bound in place of the view's lighting set when the material doesn't use it
+    empty_lighting_set: Option<Arc<dyn DescriptorSet + Send + Sync>>,
 }
 
 impl SceneObject {
@@ -30,13 +35,16 @@ impl SceneObject {
         material: Arc<dyn Material + Send + Sync>,
         mesh: Arc<dyn Mesh + Send + Sync>,
     ) -> Self {
-        let uniform_buffer_pool: CpuBufferPool<world_matrix> =
-            CpuBufferPool::uniform_buffer(device);
+        let uniform_buffer_pool = CpuBufferPool::uniform_buffer(device.clone());
+        let layout = uniforms::object_layout(device).expect("Could not create object set layout");
         SceneObject {
             uniform_buffer_pool,
+            layout,
+            empty_lighting_set: empty_lighting_set(material.as_ref()),
             transform: glm::identity(),
             material,
             mesh,
+            highlighted: false,
         }
     }
 
@@ -53,6 +61,7 @@ impl SceneObject {
     }
 
     pub fn set_material(&mut self, material: Arc<dyn Material + Send + Sync>) {
+        self.empty_lighting_set = empty_lighting_set(material.as_ref());
         self.material = material;
     }
 
@@ -62,6 +71,34 @@ impl SceneObject {
     pub fn set_mesh(&mut self, mesh: Arc<dyn Mesh + Send + Sync>) {
         self.mesh = mesh;
     }
+
+    /// Highlighted objects are outlined in the main views
+    pub fn is_highlighted(&self) -> bool {
+        self.highlighted
+    }
+
+    pub fn set_highlighted(&mut self, highlighted: bool) {
+        self.highlighted = highlighted;
+    }
+}
+
+/// Built once per material rather than every frame, the pipeline's layout leaves the set empty
+fn empty_lighting_set(
+    material: &(dyn Material + Send + Sync),
+) -> Option<Arc<dyn DescriptorSet + Send + Sync>> {
+    if material.uses_lighting() {
+        return None;
+    }
+    let layout = material
+        .pipeline()
+        .descriptor_set_layout(uniforms::LIGHTING_SET)
+        .unwrap()
+        .clone();
+    Some(Arc::new(
+        PersistentDescriptorSet::start(layout)
+            .build()
+            .expect("Could not create empty lighting set"),
+    ))
 }
 
 impl Drawable for SceneObject {
@@ -72,15 +109,12 @@ impl Drawable for SceneObject {
         view_set: Arc<dyn DescriptorSet + Send + Sync>,
         lighting_set: Arc<dyn DescriptorSet + Send + Sync>,
     ) -> Result<AutoCommandBuffer, Box<dyn error::Error + Send + Sync>> {
-        let transform_uniform_data = world_matrix {
-            world: self.transform.into(),
-        };
+        let uniforms = self
+            .uniform_buffer_pool
+            .next(ObjectUniforms::new(&self.transform))?;
 
-        let uniforms = self.uniform_buffer_pool.next(transform_uniform_data)?;
-
-        let layout = self.material.get_world_layout();
         let world_set = Arc::new(
-            PersistentDescriptorSet::start(layout)
+            PersistentDescriptorSet::start(self.layout.clone())
                 .add_buffer(uniforms)
                 .unwrap()
                 .build()
@@ -88,38 +122,41 @@ impl Drawable for SceneObject {
         );
 
         let pipeline = self.material.pipeline();
+        let lighting_set = self.empty_lighting_set.clone().unwrap_or(lighting_set);
         let mut builder = AutoCommandBufferBuilder::secondary_graphics(
             pipeline.device().clone(),
             queue.family(),
             pipeline.clone().subpass(),
         )?;
-        if self.mesh.is_indexed() {
-            builder.draw_indexed(
-                pipeline.clone(),
-                &dynamic_state,
-                vec![self.mesh.vertex_buffer()],
-                self.mesh.index_buffer(),
-                (
-                    view_set,
-                    world_set,
-                    lighting_set,
-                    self.material.material_descriptors(),
-                ),
-                (),
-            )?;
-        } else {
-            builder.draw(
-                pipeline,
-                dynamic_state,
-                vec![self.mesh.vertex_buffer()],
-                (
-                    view_set,
-                    world_set,
-                    lighting_set,
-                    self.material.material_descriptors(),
-                ),
-                (),
-            )?;
+        let main_pass = MaterialPass {
+            pipeline,
+            material_descriptors: self.material.material_descriptors(),
+        };
+        for pass in iter::once(main_pass).chain(self.material.extra_pass()) {
+            let sets = (
+                view_set.clone(),
+                world_set.clone(),
+                lighting_set.clone(),
+                pass.material_descriptors,
+            );
+            if self.mesh.is_indexed() {
+                builder.draw_indexed(
+                    pass.pipeline,
+                    dynamic_state,
+                    vec![self.mesh.vertex_buffer()],
+                    self.mesh.index_buffer(),
+                    sets,
+                    (),
+                )?;
+            } else {
+                builder.draw(
+                    pass.pipeline,
+                    dynamic_state,
+                    vec![self.mesh.vertex_buffer()],
+                    sets,
+                    (),
+                )?;
+            }
         }
         Ok(builder.build()?)
     }
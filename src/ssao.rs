@@ -0,0 +1,492 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Queue;
+use vulkano::format::{ClearValue, Format};
+use vulkano::framebuffer::{Framebuffer, FramebufferAbstract, RenderPassAbstract, Subpass};
+use vulkano::image::{AttachmentImage, Dimensions, ImageViewAccess, ImmutableImage};
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+use vulkano::sync::GpuFuture;
+
+use crate::depth::DepthConfig;
+use crate::renderer::SceneView;
+use crate::view::ViewportClear;
+use crate::Vertex;
+
+pub mod prepass_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/ssao_prepass.vert"
+    }
+}
+
+pub mod prepass_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/ssao_prepass.frag"
+    }
+}
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/fullscreen.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/ssao.frag"
+    }
+}
+
+pub mod blur_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/ssao_blur.frag"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsaoParameters {
+    /// How far around a point other surfaces occlude it, in world units
+    pub radius: f32,
+    /// 0 leaves the ambient term untouched, 1 removes it where fully occluded
+    pub intensity: f32,
+    pub sample_count: u32,
+}
+
+impl Default for SsaoParameters {
+    fn default() -> Self {
+        SsaoParameters {
+            radius: 0.5,
+            intensity: 1.0,
+            sample_count: 16,
+        }
+    }
+}
+
+type FullscreenPipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+struct SsaoTargets {
+    dimensions: [u32; 2],
+    prepass: Arc<dyn FramebufferAbstract + Send + Sync>,
+    occlusion: Arc<dyn FramebufferAbstract + Send + Sync>,
+    blurred: Arc<dyn FramebufferAbstract + Send + Sync>,
+    blurred_image: Arc<AttachmentImage>,
+    /// Depth and normals from the prepass
+    prepass_set: Arc<dyn DescriptorSet + Send + Sync>,
+    occlusion_set: Arc<dyn DescriptorSet + Send + Sync>,
+}
+
+/// Screen-space ambient occlusion of the main views. The opaque objects are drawn into a depth
+/// and view-space normal prepass, which the occlusion is sampled from and then blurred.
+/// Lit materials read the result through binding 1 of the lighting set.
+pub struct AmbientOcclusion {
+    queue: Arc<Queue>,
+    depth_config: DepthConfig,
+    parameters: SsaoParameters,
+    prepass_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    prepass_pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    prepass_clear: ViewportClear,
+    occlusion_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    pipeline: Arc<FullscreenPipeline>,
+    blur_pipeline: Arc<FullscreenPipeline>,
+    sampler: Arc<Sampler>,
+    no_occlusion: Arc<ImmutableImage<Format>>,
+    targets: SsaoTargets,
+}
+
+impl AmbientOcclusion {
+    pub fn new(
+        queue: Arc<Queue>,
+        dimensions: [u32; 2],
+        depth_config: DepthConfig,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let prepass_vs =
+            prepass_vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let prepass_fs =
+            prepass_fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+        let blur_fs =
+            blur_fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let prepass_pass: Arc<dyn RenderPassAbstract + Send + Sync> =
+            Arc::new(vulkano::single_pass_renderpass!(device.clone(),
+                attachments: {
+                    normals: {
+                        load: Clear,
+                        store: Store,
+                        format: Format::R16G16B16A16Sfloat,
+                        samples: 1,
+                    },
+                    depth: {
+                        load: Clear,
+                        store: Store,
+                        format: Format::D32Sfloat,
+                        samples: 1,
+                    }
+                },
+            pass: {
+                color: [normals],
+                depth_stencil: {depth}
+            })?);
+
+        let occlusion_pass: Arc<dyn RenderPassAbstract + Send + Sync> =
+            Arc::new(vulkano::single_pass_renderpass!(device.clone(),
+                attachments: {
+                    occlusion: {
+                        load: Clear,
+                        store: Store,
+                        format: Format::R8Unorm,
+                        samples: 1,
+                    }
+                },
+            pass: {
+                color: [occlusion],
+                depth_stencil: {}
+            })?);
+
+        let prepass_pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<Vertex>()
+                .vertex_shader(prepass_vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(prepass_fs.main_entry_point(), ())
+                .depth_stencil(depth_config.depth_stencil(true))
+                .front_face_counter_clockwise()
+                .cull_mode_back()
+                .render_pass(Subpass::from(prepass_pass.clone(), 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let prepass_clear = ViewportClear::new(device.clone(), prepass_pass.clone())?;
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .render_pass(Subpass::from(occlusion_pass.clone(), 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let blur_pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(blur_fs.main_entry_point(), ())
+                .render_pass(Subpass::from(occlusion_pass.clone(), 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let sampler = Sampler::new(
+            device,
+            Filter::Nearest,
+            Filter::Nearest,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        let (no_occlusion, future) = ImmutableImage::from_iter(
+            std::iter::once(255u8),
+            Dimensions::Dim2d {
+                width: 1,
+                height: 1,
+            },
+            Format::R8Unorm,
+            queue.clone(),
+        )?;
+        future.then_signal_fence_and_flush()?.wait(None)?;
+
+        let targets = SsaoTargets::new(
+            &queue,
+            dimensions,
+            &prepass_pass,
+            &occlusion_pass,
+            &pipeline,
+            &blur_pipeline,
+            &sampler,
+        )?;
+
+        Ok(AmbientOcclusion {
+            queue,
+            depth_config,
+            parameters: SsaoParameters::default(),
+            prepass_pass,
+            prepass_pipeline,
+            prepass_clear,
+            occlusion_pass,
+            pipeline,
+            blur_pipeline,
+            sampler,
+            no_occlusion,
+            targets,
+        })
+    }
+
+    pub fn parameters(&self) -> SsaoParameters {
+        self.parameters
+    }
+
+    pub fn set_parameters(&mut self, parameters: SsaoParameters) {
+        self.parameters = parameters;
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.targets.dimensions
+    }
+
+    /// Recreates the targets if `dimensions` differ, the occlusion is sampled at the
+    /// fragment's pixel so they must match the scene target
+    pub fn resize(
+        &mut self,
+        dimensions: [u32; 2],
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        if dimensions != self.targets.dimensions {
+            self.targets = SsaoTargets::new(
+                &self.queue,
+                dimensions,
+                &self.prepass_pass,
+                &self.occlusion_pass,
+                &self.pipeline,
+                &self.blur_pipeline,
+                &self.sampler,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The occlusion of the main views, for the lighting sets of those views
+    pub fn occlusion(&self) -> Arc<dyn ImageViewAccess + Send + Sync> {
+        self.targets.blurred_image.clone()
+    }
+
+    /// A single unoccluded pixel, for the lighting sets of views without ambient occlusion
+    pub fn no_occlusion(&self) -> Arc<dyn ImageViewAccess + Send + Sync> {
+        self.no_occlusion.clone()
+    }
+
+    /// Both `occlusion` and `no_occlusion` are meant to be sampled with this
+    pub fn sampler(&self) -> Arc<Sampler> {
+        self.sampler.clone()
+    }
+
+    /// Draws the prepass and computes the occlusion of `views`, like the main views of a frame,
+    /// outside of any render pass. Returns the number of draw calls.
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder,
+        views: &[&SceneView],
+    ) -> Result<usize, Box<dyn error::Error + Send + Sync>> {
+        let dimensions = self.targets.dimensions;
+        let queue = self.queue.clone();
+        let mut draw_calls = 0;
+
+        builder.begin_render_pass(
+            self.targets.prepass.clone(),
+            true,
+            vec![
+                ClearValue::Float([0.0, 0.0, 0.0, 0.0]),
+                self.depth_config.clear_value().into(),
+            ],
+        )?;
+        for (i, view) in views.iter().enumerate() {
+            let dynamic_state = DynamicState {
+                viewports: Some(vec![view.rect.to_viewport(dimensions)]),
+                ..DynamicState::none()
+            };
+            let mut sub_command_buffers = vec![];
+            // the render pass already cleared everything for the first view
+            if i > 0 {
+                sub_command_buffers.push(self.prepass_clear.draw(
+                    queue.clone(),
+                    &dynamic_state,
+                    [0.0, 0.0, 0.0, 0.0],
+                    self.depth_config.clear_value(),
+                )?);
+            }
+
+            let mut prepass = AutoCommandBufferBuilder::secondary_graphics(
+                queue.device().clone(),
+                queue.family(),
+                self.prepass_pipeline.clone().subpass(),
+            )?;
+            // transparent objects don't write depth, so they don't occlude either
+            for item in view.render_queue.opaque() {
+                let mesh = item.object.get_mesh();
+                let parameters = prepass_vs::ty::prepass_parameters {
+                    world: item.object.get_transform().into(),
+                };
+                if mesh.is_indexed() {
+                    prepass.draw_indexed(
+                        self.prepass_pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        mesh.index_buffer(),
+                        view.view_set.clone(),
+                        parameters,
+                    )?;
+                } else {
+                    prepass.draw(
+                        self.prepass_pipeline.clone(),
+                        &dynamic_state,
+                        vec![mesh.vertex_buffer()],
+                        view.view_set.clone(),
+                        parameters,
+                    )?;
+                }
+                draw_calls += 1;
+            }
+            sub_command_buffers.push(prepass.build()?);
+            // executing a secondary command buffer is unsafe for now
+            unsafe {
+                builder.execute_commands_from_vec(sub_command_buffers)?;
+            }
+        }
+        builder.end_render_pass()?;
+
+        builder.begin_render_pass(
+            self.targets.occlusion.clone(),
+            false,
+            vec![ClearValue::Float([1.0, 0.0, 0.0, 0.0])],
+        )?;
+        for view in views {
+            let viewport = view.rect.to_viewport(dimensions);
+            let parameters = fs::ty::ssao_parameters {
+                viewport: [
+                    viewport.origin[0],
+                    viewport.origin[1],
+                    viewport.dimensions[0],
+                    viewport.dimensions[1],
+                ],
+                radius: self.parameters.radius,
+                intensity: self.parameters.intensity,
+                sample_count: self.parameters.sample_count as i32,
+                clear_depth: self.depth_config.clear_value(),
+            };
+            let dynamic_state = DynamicState {
+                viewports: Some(vec![viewport]),
+                ..DynamicState::none()
+            };
+            builder.draw(
+                self.pipeline.clone(),
+                &dynamic_state,
+                BufferlessVertices {
+                    vertices: 3,
+                    instances: 1,
+                },
+                (view.view_set.clone(), self.targets.prepass_set.clone()),
+                parameters,
+            )?;
+        }
+        builder.end_render_pass()?;
+
+        let dynamic_state = DynamicState {
+            viewports: Some(vec![Viewport {
+                origin: [0.0, 0.0],
+                dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                depth_range: 0.0..1.0,
+            }]),
+            ..DynamicState::none()
+        };
+        builder.begin_render_pass(self.targets.blurred.clone(), false, vec![ClearValue::None])?;
+        builder.draw(
+            self.blur_pipeline.clone(),
+            &dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            self.targets.occlusion_set.clone(),
+            (),
+        )?;
+        builder.end_render_pass()?;
+
+        Ok(draw_calls)
+    }
+}
+
+impl SsaoTargets {
+    fn new(
+        queue: &Arc<Queue>,
+        dimensions: [u32; 2],
+        prepass_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        occlusion_pass: &Arc<dyn RenderPassAbstract + Send + Sync>,
+        pipeline: &Arc<FullscreenPipeline>,
+        blur_pipeline: &Arc<FullscreenPipeline>,
+        sampler: &Arc<Sampler>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let normals =
+            AttachmentImage::sampled(device.clone(), dimensions, Format::R16G16B16A16Sfloat)?;
+        let depth = AttachmentImage::sampled(device.clone(), dimensions, Format::D32Sfloat)?;
+        let occlusion = AttachmentImage::sampled(device.clone(), dimensions, Format::R8Unorm)?;
+        let blurred_image = AttachmentImage::sampled(device, dimensions, Format::R8Unorm)?;
+
+        let prepass = Arc::new(
+            Framebuffer::start(prepass_pass.clone())
+                .add(normals.clone())?
+                .add(depth.clone())?
+                .build()?,
+        );
+        let occlusion_framebuffer = Arc::new(
+            Framebuffer::start(occlusion_pass.clone())
+                .add(occlusion.clone())?
+                .build()?,
+        );
+        let blurred = Arc::new(
+            Framebuffer::start(occlusion_pass.clone())
+                .add(blurred_image.clone())?
+                .build()?,
+        );
+
+        let layout = pipeline.descriptor_set_layout(1).unwrap();
+        let prepass_set = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_sampled_image(depth, sampler.clone())?
+                .add_sampled_image(normals, sampler.clone())?
+                .build()?,
+        );
+        let layout = blur_pipeline.descriptor_set_layout(0).unwrap();
+        let occlusion_set = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_sampled_image(occlusion, sampler.clone())?
+                .build()?,
+        );
+
+        Ok(SsaoTargets {
+            dimensions,
+            prepass,
+            occlusion: occlusion_framebuffer,
+            blurred,
+            blurred_image,
+            prepass_set,
+            occlusion_set,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::renderer::RenderStats;
+use crate::text::TextDraw;
+
+const TEXT_SCALE: f32 = 2.0;
+const MARGIN: f32 = 8.0;
+
+/// Frame timing, renderer counters, and the camera, drawn in the top left corner
+pub struct StatsOverlay {
+    visible: bool,
+    average_frame_time: f32,
+}
+
+impl Default for StatsOverlay {
+    fn default() -> Self {
+        StatsOverlay {
+            visible: true,
+            average_frame_time: 0.0,
+        }
+    }
+}
+
+impl StatsOverlay {
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Smooths the frame time so the numbers are readable
+    pub fn update(&mut self, frame_time: f32) {
+        if self.average_frame_time == 0.0 {
+            self.average_frame_time = frame_time;
+        } else {
+            self.average_frame_time += (frame_time - self.average_frame_time) * 0.05;
+        }
+    }
+
+    pub fn draw(&self, text: &mut TextDraw, render_stats: RenderStats, camera: &Camera) {
+        if !self.visible {
+            return;
+        }
+
+        let fps = if self.average_frame_time > 0.0 {
+            1.0 / self.average_frame_time
+        } else {
+            0.0
+        };
+        let position = camera.position();
+        let lines = format!(
+            "FPS: {:.1}\nFrame time: {:.2} ms\nDraw calls: {}\nTriangles: {}\nCamera: ({:.2}, {:.2}, {:.2})\nYaw/Pitch: {:.1} / {:.1}",
+            fps,
+            self.average_frame_time * 1000.0,
+            render_stats.draw_calls,
+            render_stats.triangles,
+            position.x,
+            position.y,
+            position.z,
+            camera.yaw(),
+            camera.pitch(),
+        );
+        text.text_with_shadow(
+            [MARGIN, MARGIN],
+            TEXT_SCALE,
+            glm::vec4(1.0, 1.0, 1.0, 1.0),
+            &lines,
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::buffer::CpuBufferPool;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::image::{Dimensions, ImmutableImage};
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+use vulkano::sync::GpuFuture;
+
+use nalgebra_glm as glm;
+
+use crate::font;
+use crate::material::BlendMode;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/text.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/text.frag"
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct TextVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+vulkano::impl_vertex!(TextVertex, position, uv, color);
+
+/// Text collected over a frame, drawn over the final image and then cleared
+#[derive(Default)]
+pub struct TextDraw {
+    vertices: Vec<TextVertex>,
+}
+
+impl TextDraw {
+    /// Height of a line of text drawn at `scale`, in pixels
+    pub fn line_height(scale: f32) -> f32 {
+        (font::GLYPH_SIZE + 2) as f32 * scale
+    }
+
+    /// Draws `text` with its top left corner at `position` in pixels,
+    /// each glyph is `scale` times the font's 8x8 pixels
+    pub fn text(&mut self, position: [f32; 2], scale: f32, color: glm::Vec4, text: &str) {
+        let [atlas_width, atlas_height] = font::atlas_dimensions();
+        let glyph_size = font::GLYPH_SIZE as f32 * scale;
+        let uv_width = font::GLYPH_SIZE as f32 / atlas_width as f32;
+        let uv_height = font::GLYPH_SIZE as f32 / atlas_height as f32;
+        let color: [f32; 4] = color.into();
+
+        let mut x = position[0];
+        let mut y = position[1];
+        for c in text.chars() {
+            if c == '\n' {
+                x = position[0];
+                y += TextDraw::line_height(scale);
+                continue;
+            }
+            if c != ' ' {
+                let [column, row] = font::glyph_cell(c);
+                let u = column as f32 * uv_width;
+                let v = row as f32 * uv_height;
+                let corner = |dx: f32, dy: f32| TextVertex {
+                    position: [x + dx * glyph_size, y + dy * glyph_size],
+                    uv: [u + dx * uv_width, v + dy * uv_height],
+                    color,
+                };
+                self.vertices.extend_from_slice(&[
+                    corner(0.0, 0.0),
+                    corner(1.0, 0.0),
+                    corner(1.0, 1.0),
+                    corner(0.0, 0.0),
+                    corner(1.0, 1.0),
+                    corner(0.0, 1.0),
+                ]);
+            }
+            x += glyph_size;
+        }
+    }
+
+    /// Like `text`, with a dark copy one glyph pixel behind it to stay readable over the scene
+    pub fn text_with_shadow(
+        &mut self,
+        position: [f32; 2],
+        scale: f32,
+        color: glm::Vec4,
+        text: &str,
+    ) {
+        let shadow = glm::vec4(0.0, 0.0, 0.0, color.w);
+        self.text(
+            [position[0] + scale, position[1] + scale],
+            scale,
+            shadow,
+            text,
+        );
+        self.text(position, scale, color, text);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+}
+
+/// Draws a `TextDraw` as textured quads sampling the font atlas
+pub struct TextRenderer {
+    pipeline: Arc<dyn GraphicsPipelineAbstract + Send + Sync>,
+    atlas_set: Arc<dyn DescriptorSet + Send + Sync>,
+    vertex_buffer_pool: CpuBufferPool<TextVertex>,
+}
+
+impl TextRenderer {
+    /// Waits for the font atlas to be uploaded
+    pub fn new(
+        queue: Arc<Queue>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input_single_buffer::<TextVertex>()
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .blend_collective(BlendMode::AlphaBlend.attachment_blend())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let [width, height] = font::atlas_dimensions();
+        let (atlas, future) = ImmutableImage::from_iter(
+            font::atlas_pixels().into_iter(),
+            Dimensions::Dim2d { width, height },
+            Format::R8Unorm,
+            queue,
+        )?;
+        future.then_signal_fence_and_flush()?.wait(None)?;
+
+        let sampler = Sampler::new(
+            device.clone(),
+            Filter::Nearest,
+            Filter::Nearest,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        let layout = pipeline.descriptor_set_layout(0).unwrap();
+        let atlas_set = Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_sampled_image(atlas, sampler)?
+                .build()?,
+        );
+
+        Ok(TextRenderer {
+            pipeline,
+            atlas_set,
+            vertex_buffer_pool: CpuBufferPool::vertex_buffer(device),
+        })
+    }
+
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        screen_size: [f32; 2],
+        text: &TextDraw,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let vertex_buffer = self
+            .vertex_buffer_pool
+            .chunk(text.vertices.iter().cloned())?;
+        builder.draw(
+            self.pipeline.clone(),
+            dynamic_state,
+            vec![Arc::new(vertex_buffer)],
+            self.atlas_set.clone(),
+            vs::ty::text_parameters { screen_size },
+        )?;
+        Ok(())
+    }
+}
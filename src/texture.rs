@@ -0,0 +1,378 @@
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::command_buffer::{
+    AutoCommandBuffer, AutoCommandBufferBuilder, CommandBuffer, CommandBufferExecFuture,
+};
+use vulkano::device::{Device, Queue};
+use vulkano::format::Format;
+use vulkano::image::{
+    Dimensions, ImageLayout, ImageUsage, ImageViewAccess, ImmutableImage, MipmapsCount,
+};
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode, SamplerCreationError};
+use vulkano::sync::{GpuFuture, NowFuture};
+
+#[derive(Debug)]
+pub enum TextureError {
+    IoError(io::Error),
+    PngError(png::DecodingError),
+    JpegError(jpeg_decoder::Error),
+    UnsupportedFormat,
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TextureError::IoError(ref e) => e.fmt(f),
+            TextureError::PngError(ref e) => e.fmt(f),
+            TextureError::JpegError(ref e) => e.fmt(f),
+            TextureError::UnsupportedFormat => write!(f, "Image is neither a PNG nor a JPEG"),
+        }
+    }
+}
+
+impl error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            TextureError::IoError(ref e) => Some(e),
+            TextureError::PngError(ref e) => Some(e),
+            TextureError::JpegError(ref e) => Some(e),
+            TextureError::UnsupportedFormat => None,
+        }
+    }
+}
+
+impl From<io::Error> for TextureError {
+    fn from(err: io::Error) -> TextureError {
+        TextureError::IoError(err)
+    }
+}
+
+impl From<png::DecodingError> for TextureError {
+    fn from(err: png::DecodingError) -> TextureError {
+        TextureError::PngError(err)
+    }
+}
+
+impl From<jpeg_decoder::Error> for TextureError {
+    fn from(err: jpeg_decoder::Error) -> TextureError {
+        TextureError::JpegError(err)
+    }
+}
+
+/// Decoded 8 bit RGBA pixels, row by row from the top
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageData {
+    pub pixels: Vec<u8>,
+    pub dimensions: [u32; 2],
+}
+
+impl ImageData {
+    /// A single pixel of `color`
+    pub fn solid(color: [u8; 4]) -> Self {
+        ImageData {
+            pixels: color.to_vec(),
+            dimensions: [1, 1],
+        }
+    }
+
+    /// Reads a PNG or JPEG file, telling them apart by their contents
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, TextureError> {
+        ImageData::decode(&fs::read(path)?)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, TextureError> {
+        if bytes.starts_with(b"\x89PNG") {
+            ImageData::decode_png(bytes)
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            ImageData::decode_jpeg(bytes)
+        } else {
+            Err(TextureError::UnsupportedFormat)
+        }
+    }
+
+    fn decode_png(bytes: &[u8]) -> Result<Self, TextureError> {
+        let mut decoder = png::Decoder::new(bytes);
+        // palettes, low bit depths and 16 bit channels all come out as 8 bit
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => rgba_from(&buffer, 3, |p| [p[0], p[1], p[2], 255]),
+            png::ColorType::GrayscaleAlpha => rgba_from(&buffer, 2, |p| [p[0], p[0], p[0], p[1]]),
+            png::ColorType::Grayscale => rgba_from(&buffer, 1, |p| [p[0], p[0], p[0], 255]),
+            png::ColorType::Indexed => return Err(TextureError::UnsupportedFormat),
+        };
+        Ok(ImageData {
+            pixels,
+            dimensions: [info.width, info.height],
+        })
+    }
+
+    fn decode_jpeg(bytes: &[u8]) -> Result<Self, TextureError> {
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let buffer = decoder.decode()?;
+        let info = decoder.info().ok_or(TextureError::UnsupportedFormat)?;
+
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::RGB24 => rgba_from(&buffer, 3, |p| [p[0], p[1], p[2], 255]),
+            jpeg_decoder::PixelFormat::L8 => rgba_from(&buffer, 1, |p| [p[0], p[0], p[0], 255]),
+            // big endian, keep the high byte
+            jpeg_decoder::PixelFormat::L16 => rgba_from(&buffer, 2, |p| [p[0], p[0], p[0], 255]),
+            jpeg_decoder::PixelFormat::CMYK32 => rgba_from(&buffer, 4, |p| {
+                let black = 255 - p[3] as u32;
+                let channel = |c: u8| ((255 - c as u32) * black / 255) as u8;
+                [channel(p[0]), channel(p[1]), channel(p[2]), 255]
+            }),
+        };
+        Ok(ImageData {
+            pixels,
+            dimensions: [info.width as u32, info.height as u32],
+        })
+    }
+}
+
+fn rgba_from<F>(buffer: &[u8], channels: usize, pixel: F) -> Vec<u8>
+where
+    F: Fn(&[u8]) -> [u8; 4],
+{
+    buffer.chunks_exact(channels).flat_map(pixel).collect()
+}
+
+/// Whether the pixels are colors, which are stored in sRGB, or other data such as normals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    fn format(&self) -> Format {
+        match *self {
+            ColorSpace::Srgb => Format::R8G8B8A8Srgb,
+            ColorSpace::Linear => Format::R8G8B8A8Unorm,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerParameters {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_mode: MipmapMode,
+    pub address_mode: SamplerAddressMode,
+    /// 1 disables anisotropic filtering, anything above is clamped to what the device allows,
+    /// and to 1 without the `sampler_anisotropy` feature
+    pub max_anisotropy: f32,
+}
+
+impl Default for SamplerParameters {
+    fn default() -> Self {
+        SamplerParameters {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: MipmapMode::Linear,
+            address_mode: SamplerAddressMode::Repeat,
+            max_anisotropy: 1.0,
+        }
+    }
+}
+
+impl SamplerParameters {
+    pub fn create(&self, device: Arc<Device>) -> Result<Arc<Sampler>, SamplerCreationError> {
+        let max_anisotropy = if device.enabled_features().sampler_anisotropy {
+            let limit = device.physical_device().limits().max_sampler_anisotropy();
+            self.max_anisotropy.max(1.0).min(limit)
+        } else {
+            1.0
+        };
+        Sampler::new(
+            device,
+            self.mag_filter,
+            self.min_filter,
+            self.mipmap_mode,
+            self.address_mode,
+            self.address_mode,
+            self.address_mode,
+            0.0,
+            max_anisotropy,
+            0.0,
+            // every mipmap level
+            1000.0,
+        )
+    }
+}
+
+pub type TextureAndFuture = (
+    Arc<Texture>,
+    CommandBufferExecFuture<NowFuture, AutoCommandBuffer>,
+);
+
+/// An immutable, mipmapped image and the sampler to read it with
+pub struct Texture {
+    image: Arc<ImmutableImage<Format>>,
+    sampler: Arc<Sampler>,
+    dimensions: [u32; 2],
+}
+
+impl Texture {
+    /// Loads a PNG or JPEG file, the texture can be used once the future completes
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        color_space: ColorSpace,
+        sampler: &SamplerParameters,
+        queue: Arc<Queue>,
+    ) -> Result<TextureAndFuture, Box<dyn error::Error + Send + Sync>> {
+        Texture::new(&ImageData::open(path)?, color_space, sampler, queue)
+    }
+
+    /// Uploads `data` along with mipmaps down to a single pixel
+    pub fn new(
+        data: &ImageData,
+        color_space: ColorSpace,
+        sampler: &SamplerParameters,
+        queue: Arc<Queue>,
+    ) -> Result<TextureAndFuture, Box<dyn error::Error + Send + Sync>> {
+        let device = queue.device().clone();
+        let [width, height] = data.dimensions;
+        let (image, initialization) = ImmutableImage::uninitialized(
+            device.clone(),
+            Dimensions::Dim2d { width, height },
+            color_space.format(),
+            MipmapsCount::Log2,
+            ImageUsage {
+                transfer_destination: true,
+                sampled: true,
+                ..ImageUsage::none()
+            },
+            ImageLayout::ShaderReadOnlyOptimal,
+            device.active_queue_families(),
+        )?;
+        let initialization = Arc::new(initialization);
+
+        // vulkano can't blit between levels of the same image, so the levels are made here
+        let mut builder = AutoCommandBufferBuilder::new(device.clone(), queue.family())?;
+        let mut pixels = data.pixels.clone();
+        let mut dimensions = data.dimensions;
+        for level in 0..image.mipmap_levels() {
+            if level > 0 {
+                pixels = downsample(&pixels, dimensions, color_space);
+                dimensions = [(dimensions[0] / 2).max(1), (dimensions[1] / 2).max(1)];
+            }
+            let source = CpuAccessibleBuffer::from_iter(
+                device.clone(),
+                BufferUsage::transfer_source(),
+                false,
+                pixels.iter().cloned(),
+            )?;
+            builder.copy_buffer_to_image_dimensions(
+                source,
+                initialization.clone(),
+                [0, 0, 0],
+                [dimensions[0], dimensions[1], 1],
+                0,
+                1,
+                level,
+            )?;
+        }
+        let future = builder.build()?.execute(queue)?;
+
+        let texture = Arc::new(Texture {
+            image,
+            sampler: sampler.create(device)?,
+            dimensions: data.dimensions,
+        });
+        Ok((texture, future))
+    }
+
+    pub fn image(&self) -> Arc<dyn ImageViewAccess + Send + Sync> {
+        self.image.clone()
+    }
+
+    pub fn sampler(&self) -> Arc<Sampler> {
+        self.sampler.clone()
+    }
+
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+}
+
+/// A normal map leaving the surface normal as it is, for materials that were not given one
+pub fn flat_normal_map(
+    queue: Arc<Queue>,
+) -> Result<Arc<Texture>, Box<dyn error::Error + Send + Sync>> {
+    let (texture, future) = Texture::new(
+        &ImageData::solid([128, 128, 255, 255]),
+        ColorSpace::Linear,
+        &SamplerParameters::default(),
+        queue,
+    )?;
+    future.then_signal_fence_and_flush()?.wait(None)?;
+    Ok(texture)
+}
+
+/// A white map, which leaves the color it is multiplied with as it is
+pub fn white_map(queue: Arc<Queue>) -> Result<Arc<Texture>, Box<dyn error::Error + Send + Sync>> {
+    let (texture, future) = Texture::new(
+        &ImageData::solid([255, 255, 255, 255]),
+        ColorSpace::Srgb,
+        &SamplerParameters::default(),
+        queue,
+    )?;
+    future.then_signal_fence_and_flush()?.wait(None)?;
+    Ok(texture)
+}
+
+/// Halves `pixels` with a box filter, averaging colors in linear space
+fn downsample(pixels: &[u8], dimensions: [u32; 2], color_space: ColorSpace) -> Vec<u8> {
+    let [width, height] = dimensions;
+    let (new_width, new_height) = ((width / 2).max(1), (height / 2).max(1));
+    let to_linear = |value: u8, channel: usize| {
+        let value = value as f32 / 255.0;
+        if color_space == ColorSpace::Linear || channel == 3 {
+            value
+        } else if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let from_linear = |value: f32, channel: usize| {
+        let value = if color_space == ColorSpace::Linear || channel == 3 {
+            value
+        } else if value <= 0.0031308 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        };
+        (value * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+
+    let mut result = Vec::with_capacity((new_width * new_height * 4) as usize);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            for channel in 0..4 {
+                let mut sum = 0.0;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                    // odd sizes repeat the last row or column
+                    let source_x = (x * 2 + dx).min(width - 1);
+                    let source_y = (y * 2 + dy).min(height - 1);
+                    let index = ((source_y * width + source_x) * 4) as usize + channel;
+                    sum += to_linear(pixels[index], channel);
+                }
+                result.push(from_linear(sum / 4.0, channel));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,265 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::descriptor::descriptor::{
+    DescriptorBufferDesc, DescriptorDesc, DescriptorDescTy, DescriptorImageDesc,
+    DescriptorImageDescArray, DescriptorImageDescDimensions, ShaderStages,
+};
+use vulkano::descriptor::descriptor_set::UnsafeDescriptorSetLayout;
+use vulkano::descriptor::pipeline_layout::{
+    PipelineLayout, PipelineLayoutDesc, PipelineLayoutDescPcRange, RuntimePipelineDesc,
+};
+use vulkano::device::Device;
+use vulkano::OomError;
+
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::depth::DepthConfig;
+use crate::fog::{Fog, FogMode, HeightFog};
+use crate::light::PointLight;
+
+/// Sets 0 to 2 are filled by the engine and shared by every material, a material only
+/// declares set 3. Shaders include `view.glsl`, `object.glsl`, and `lighting.glsl` for them.
+pub const VIEW_SET: usize = 0;
+pub const OBJECT_SET: usize = 1;
+pub const LIGHTING_SET: usize = 2;
+pub const MATERIAL_SET: usize = 3;
+
+/// `view_matrices` in `view.glsl`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewUniforms {
+    pub view: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+}
+
+impl ViewUniforms {
+    pub fn new(camera: &Camera, aspect_ratio: f32, depth_config: &DepthConfig) -> Self {
+        ViewUniforms {
+            view: camera.get_view_matrix().into(),
+            projection: camera.projection_matrix(aspect_ratio, depth_config).into(),
+        }
+    }
+}
+
+/// `world_matrix` in `object.glsl`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjectUniforms {
+    pub world: [[f32; 4]; 4],
+}
+
+impl ObjectUniforms {
+    pub fn new(world: &glm::Mat4) -> Self {
+        ObjectUniforms {
+            world: (*world).into(),
+        }
+    }
+}
+
+// std140 pads every vec3 and struct to 16 bytes
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LightUniform {
+    position: [f32; 3],
+    _padding0: f32,
+    ambient: [f32; 3],
+    _padding1: f32,
+    diffuse: [f32; 3],
+    _padding2: f32,
+    specular: [f32; 3],
+    _padding3: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FogUniform {
+    color: [f32; 3],
+    mode: i32,
+    start: f32,
+    end: f32,
+    density: f32,
+    height_density: f32,
+    height_falloff: f32,
+    base_height: f32,
+    _padding: [f32; 2],
+}
+
+/// `light_parameters` in `lighting.glsl`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightingUniforms {
+    view_position: [f32; 3],
+    _padding: f32,
+    light: LightUniform,
+    fog: FogUniform,
+}
+
+impl LightingUniforms {
+    /// `fog_color` should be the background's, so that objects fade into it
+    pub fn new(
+        view_position: glm::Vec3,
+        light: &PointLight,
+        fog: &Fog,
+        fog_color: glm::Vec3,
+    ) -> Self {
+        let (mode, start, end, density) = match fog.mode {
+            FogMode::None => (0, 0.0, 0.0, 0.0),
+            FogMode::Linear { start, end } => (1, start, end, 0.0),
+            FogMode::Exponential { density } => (2, 0.0, 0.0, density),
+            FogMode::ExponentialSquared { density } => (3, 0.0, 0.0, density),
+        };
+        let height = fog.height.unwrap_or(HeightFog {
+            density: 0.0,
+            ..HeightFog::default()
+        });
+
+        LightingUniforms {
+            view_position: view_position.into(),
+            _padding: 0.0,
+            light: LightUniform {
+                position: light.position.into(),
+                _padding0: 0.0,
+                ambient: light.ambient.into(),
+                _padding1: 0.0,
+                diffuse: light.diffuse.into(),
+                _padding2: 0.0,
+                specular: light.specular.into(),
+                _padding3: 0.0,
+            },
+            fog: FogUniform {
+                color: fog_color.into(),
+                mode,
+                start,
+                end,
+                density,
+                height_density: height.density,
+                height_falloff: height.falloff,
+                base_height: height.base_height,
+                _padding: [0.0; 2],
+            },
+        }
+    }
+}
+
+fn stages() -> ShaderStages {
+    ShaderStages {
+        vertex: true,
+        fragment: true,
+        ..ShaderStages::none()
+    }
+}
+
+fn uniform_buffer() -> Option<DescriptorDesc> {
+    Some(DescriptorDesc {
+        ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+            dynamic: Some(false),
+            storage: false,
+        }),
+        array_count: 1,
+        stages: stages(),
+        readonly: true,
+    })
+}
+
+fn sampled_image() -> Option<DescriptorDesc> {
+    Some(DescriptorDesc {
+        ty: DescriptorDescTy::CombinedImageSampler(DescriptorImageDesc {
+            sampled: true,
+            dimensions: DescriptorImageDescDimensions::TwoDimensional,
+            format: None,
+            multisampled: false,
+            array_layers: DescriptorImageDescArray::NonArrayed,
+        }),
+        array_count: 1,
+        stages: stages(),
+        readonly: true,
+    })
+}
+
+fn view_descriptors() -> Vec<Option<DescriptorDesc>> {
+    vec![uniform_buffer()]
+}
+
+fn object_descriptors() -> Vec<Option<DescriptorDesc>> {
+    vec![uniform_buffer()]
+}
+
+// the light and fog, then the ambient occlusion
+fn lighting_descriptors() -> Vec<Option<DescriptorDesc>> {
+    vec![uniform_buffer(), sampled_image()]
+}
+
+/// The layout of the object set, each `SceneObject` builds its set against it
+pub fn object_layout(device: Arc<Device>) -> Result<Arc<UnsafeDescriptorSetLayout>, OomError> {
+    Ok(Arc::new(UnsafeDescriptorSetLayout::new(
+        device,
+        object_descriptors(),
+    )?))
+}
+
+/// The layout material pipelines are built with. Sets 0 to 2 are defined here rather than
+/// by what the shaders use, so the sets the engine binds match them exactly, set 3 and any
+/// push constants are the shaders'. Without `lighting` set 2 is left empty.
+pub fn material_layout<V, F>(
+    device: Arc<Device>,
+    vertex_shader: &V,
+    fragment_shader: &F,
+    lighting: bool,
+) -> Result<PipelineLayout<RuntimePipelineDesc>, Box<dyn error::Error + Send + Sync>>
+where
+    V: PipelineLayoutDesc + Clone,
+    F: PipelineLayoutDesc + Clone,
+{
+    let shaders = vertex_shader.clone().union(fragment_shader.clone());
+    let material = (0..shaders.num_bindings_in_set(MATERIAL_SET).unwrap_or(0))
+        .map(|binding| shaders.descriptor(MATERIAL_SET, binding))
+        .collect();
+    let push_constants: Vec<PipelineLayoutDescPcRange> = (0..shaders.num_push_constants_ranges())
+        .filter_map(|range| shaders.push_constants_range(range))
+        .collect();
+    let lighting = if lighting {
+        lighting_descriptors()
+    } else {
+        vec![]
+    };
+
+    let desc = RuntimePipelineDesc::new(
+        vec![view_descriptors(), object_descriptors(), lighting, material],
+        push_constants,
+    )?;
+    Ok(PipelineLayout::new(device, desc)?)
+}
+
+/// The layouts of the sets filled once per view, the same as those of every pipeline built
+/// with `material_layout`
+pub struct SceneLayouts {
+    view: Arc<UnsafeDescriptorSetLayout>,
+    lighting: Arc<UnsafeDescriptorSetLayout>,
+}
+
+impl SceneLayouts {
+    pub fn new(device: Arc<Device>) -> Result<Self, OomError> {
+        let view = Arc::new(UnsafeDescriptorSetLayout::new(
+            device.clone(),
+            view_descriptors(),
+        )?);
+        let lighting = Arc::new(UnsafeDescriptorSetLayout::new(
+            device,
+            lighting_descriptors(),
+        )?);
+        Ok(SceneLayouts { view, lighting })
+    }
+
+    /// Holds a buffer of `ViewUniforms`
+    pub fn view(&self) -> Arc<UnsafeDescriptorSetLayout> {
+        self.view.clone()
+    }
+
+    /// Holds a buffer of `LightingUniforms` and the ambient occlusion
+    pub fn lighting(&self) -> Arc<UnsafeDescriptorSetLayout> {
+        self.lighting.clone()
+    }
+}
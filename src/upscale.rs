@@ -0,0 +1,142 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DrawError, DynamicState};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::{DescriptorSet, PipelineLayoutAbstract};
+use vulkano::device::Device;
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::image::AttachmentImage;
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/fullscreen.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/upscale.frag"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpscaleFilter {
+    Bilinear,
+    /// Bilinear followed by an unsharp mask, `strength` around 0.1 - 0.3 works well
+    Sharpen {
+        strength: f32,
+    },
+}
+
+impl UpscaleFilter {
+    fn sharpness(&self) -> f32 {
+        match *self {
+            UpscaleFilter::Bilinear => 0.0,
+            UpscaleFilter::Sharpen { strength } => strength,
+        }
+    }
+}
+
+type UpscalePipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+/// Draws a sampled image over the whole of the current viewport
+pub struct Upscaler {
+    pipeline: Arc<UpscalePipeline>,
+    sampler: Arc<Sampler>,
+    filter: UpscaleFilter,
+}
+
+impl Upscaler {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+        filter: UpscaleFilter,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device.clone())?,
+        );
+
+        let sampler = Sampler::new(
+            device,
+            Filter::Linear,
+            Filter::Linear,
+            MipmapMode::Nearest,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            SamplerAddressMode::ClampToEdge,
+            0.0,
+            1.0,
+            0.0,
+            0.0,
+        )?;
+
+        Ok(Upscaler {
+            pipeline,
+            sampler,
+            filter,
+        })
+    }
+
+    pub fn filter(&self) -> UpscaleFilter {
+        self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: UpscaleFilter) {
+        self.filter = filter;
+    }
+
+    pub fn source_set(
+        &self,
+        source: Arc<AttachmentImage>,
+    ) -> Result<Arc<dyn DescriptorSet + Send + Sync>, Box<dyn error::Error + Send + Sync>> {
+        let layout = self.pipeline.descriptor_set_layout(0).unwrap();
+        Ok(Arc::new(
+            PersistentDescriptorSet::start(layout.clone())
+                .add_sampled_image(source, self.sampler.clone())?
+                .build()?,
+        ))
+    }
+
+    pub fn draw(
+        &self,
+        builder: &mut AutoCommandBufferBuilder,
+        dynamic_state: &DynamicState,
+        source_set: Arc<dyn DescriptorSet + Send + Sync>,
+    ) -> Result<(), DrawError> {
+        let push_constants = fs::ty::upscale_parameters {
+            sharpness: self.filter.sharpness(),
+        };
+
+        builder.draw(
+            self.pipeline.clone(),
+            dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            source_set,
+            push_constants,
+        )?;
+        Ok(())
+    }
+}
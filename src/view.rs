@@ -0,0 +1,185 @@
+use std::error;
+use std::sync::Arc;
+
+use vulkano::command_buffer::{AutoCommandBuffer, AutoCommandBufferBuilder, DynamicState};
+use vulkano::descriptor::PipelineLayoutAbstract;
+use vulkano::device::{Device, Queue};
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::pipeline::depth_stencil::{Compare, DepthStencil};
+use vulkano::pipeline::vertex::{BufferlessDefinition, BufferlessVertices};
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+
+use crate::camera::Camera;
+
+pub mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/fullscreen.vert"
+    }
+}
+
+pub mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/clear.frag"
+    }
+}
+
+/// A rectangle in normalized coordinates of the render target, with the origin at the top left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for ViewportRect {
+    fn default() -> Self {
+        ViewportRect::full()
+    }
+}
+
+impl ViewportRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        ViewportRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn full() -> Self {
+        ViewportRect::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    /// Left and right halves
+    pub fn side_by_side() -> [ViewportRect; 2] {
+        [
+            ViewportRect::new(0.0, 0.0, 0.5, 1.0),
+            ViewportRect::new(0.5, 0.0, 0.5, 1.0),
+        ]
+    }
+
+    /// The full target, and an inset in the top right corner `size` times the target's size
+    pub fn picture_in_picture(size: f32, margin: f32) -> [ViewportRect; 2] {
+        [
+            ViewportRect::full(),
+            ViewportRect::new(1.0 - size - margin, margin, size, size),
+        ]
+    }
+
+    /// Quarters in reading order: top left, top right, bottom left, bottom right
+    pub fn quad() -> [ViewportRect; 4] {
+        [
+            ViewportRect::new(0.0, 0.0, 0.5, 0.5),
+            ViewportRect::new(0.5, 0.0, 0.5, 0.5),
+            ViewportRect::new(0.0, 0.5, 0.5, 0.5),
+            ViewportRect::new(0.5, 0.5, 0.5, 0.5),
+        ]
+    }
+
+    /// Whether `point`, normalized like the rect, is inside it
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.x
+            && point[0] < self.x + self.width
+            && point[1] >= self.y
+            && point[1] < self.y + self.height
+    }
+
+    pub fn aspect_ratio(&self, dimensions: [u32; 2]) -> f32 {
+        (self.width * dimensions[0] as f32) / (self.height * dimensions[1] as f32)
+    }
+
+    pub fn to_viewport(&self, dimensions: [u32; 2]) -> Viewport {
+        let width = dimensions[0] as f32;
+        let height = dimensions[1] as f32;
+        Viewport {
+            origin: [(self.x * width).round(), (self.y * height).round()],
+            dimensions: [
+                (self.width * width).round().max(1.0),
+                (self.height * height).round().max(1.0),
+            ],
+            depth_range: 0.0..1.0,
+        }
+    }
+}
+
+/// A camera and the part of the screen it is shown in
+#[derive(Clone, Debug)]
+pub struct CameraView {
+    pub camera: Camera,
+    pub rect: ViewportRect,
+}
+
+impl CameraView {
+    pub fn new(camera: Camera, rect: ViewportRect) -> Self {
+        CameraView { camera, rect }
+    }
+}
+
+type ClearPipeline = GraphicsPipeline<
+    BufferlessDefinition,
+    Box<dyn PipelineLayoutAbstract + Send + Sync>,
+    Arc<dyn RenderPassAbstract + Send + Sync>,
+>;
+
+/// Clears the color and depth inside the current viewport only, so that views
+/// can overlap others drawn earlier in the same render pass
+pub struct ViewportClear {
+    pipeline: Arc<ClearPipeline>,
+}
+
+impl ViewportClear {
+    pub fn new(
+        device: Arc<Device>,
+        render_pass: Arc<dyn RenderPassAbstract + Send + Sync>,
+    ) -> Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let vs = vs::Shader::load(device.clone()).expect("failed to create shader module");
+        let fs = fs::Shader::load(device.clone()).expect("failed to create shader module");
+
+        let pipeline = Arc::new(
+            GraphicsPipeline::start()
+                .vertex_input(BufferlessDefinition)
+                .vertex_shader(vs.main_entry_point(), ())
+                .triangle_list()
+                .viewports_dynamic_scissors_irrelevant(1)
+                .fragment_shader(fs.main_entry_point(), ())
+                .depth_stencil(DepthStencil {
+                    depth_compare: Compare::Always,
+                    ..DepthStencil::simple_depth_test()
+                })
+                .render_pass(Subpass::from(render_pass, 0).unwrap())
+                .build(device)?,
+        );
+
+        Ok(ViewportClear { pipeline })
+    }
+
+    pub fn draw(
+        &self,
+        queue: Arc<Queue>,
+        dynamic_state: &DynamicState,
+        color: [f32; 4],
+        depth: f32,
+    ) -> Result<AutoCommandBuffer, Box<dyn error::Error + Send + Sync>> {
+        let mut builder = AutoCommandBufferBuilder::secondary_graphics(
+            self.pipeline.device().clone(),
+            queue.family(),
+            self.pipeline.clone().subpass(),
+        )?;
+        builder.draw(
+            self.pipeline.clone(),
+            dynamic_state,
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            (),
+            fs::ty::clear_parameters { color, depth },
+        )?;
+        Ok(builder.build()?)
+    }
+}
@@ -0,0 +1,313 @@
+//! Renders known scenes without a window and compares them with the images in `tests/reference`.
+//!
+//! The tests need a Vulkan device, so they are ignored by default and fail without one, run them
+//! with `cargo test --test golden -- --ignored`. Any device works, a software driver such as
+//! lavapipe keeps the results stable across machines. After a change that is meant to alter the
+//! output, run them with `UPDATE_GOLDEN=1` to write new reference images.
+
+use std::env;
+use std::error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+use vulkano::descriptor::descriptor_set::PersistentDescriptorSet;
+use vulkano::descriptor::DescriptorSet;
+use vulkano::device::{DeviceExtensions, Features};
+use vulkano::instance::InstanceExtensions;
+use vulkano::sync::GpuFuture;
+
+use nalgebra_glm as glm;
+
+use vulkan_test::camera::Camera;
+use vulkan_test::context::RenderContext;
+use vulkan_test::depth::DepthConfig;
+use vulkan_test::fog::Fog;
+use vulkan_test::headless::HeadlessRenderer;
+use vulkan_test::light::PointLight;
+use vulkan_test::material::phong::{Phong, PhongParameters};
+use vulkan_test::mesh::cube::Cube;
+use vulkan_test::render_queue::RenderQueue;
+use vulkan_test::renderer::SceneView;
+use vulkan_test::scene::{SceneGraph, SceneObject};
+use vulkan_test::uniforms::{LightingUniforms, ViewUniforms};
+use vulkan_test::view::ViewportRect;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error + Send + Sync>>;
+
+const DIMENSIONS: [u32; 2] = [256, 256];
+/// Largest difference allowed in any channel of a pixel
+const TOLERANCE: u8 = 2;
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/reference")
+        .join(format!("{}.png", name))
+}
+
+fn output_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target/golden")
+        .join(format!("{}.png", name))
+}
+
+fn read_png(path: &Path) -> Result<(Vec<u8>, [u32; 2])> {
+    let decoder = png::Decoder::new(File::open(path)?);
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels)?;
+    if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+        return Err(format!("{} is not 8 bit RGBA", path.display()).into());
+    }
+    pixels.truncate(info.buffer_size());
+    Ok((pixels, [info.width, info.height]))
+}
+
+fn write_png(path: &Path, pixels: &[u8], dimensions: [u32; 2]) -> Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        dimensions[0],
+        dimensions[1],
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(pixels)?;
+    Ok(())
+}
+
+/// Fails if any pixel differs from the reference by more than `TOLERANCE`, after writing the
+/// rendered image and a diff with those pixels in red to `target/golden`
+fn compare_with_reference(name: &str, pixels: &[u8], dimensions: [u32; 2]) -> Result<()> {
+    let reference_path = reference_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        return write_png(&reference_path, pixels, dimensions);
+    }
+
+    let (reference, reference_dimensions) = read_png(&reference_path).map_err(|e| {
+        format!(
+            "could not read {} ({}), run with UPDATE_GOLDEN=1 to create it",
+            reference_path.display(),
+            e
+        )
+    })?;
+    if reference_dimensions != dimensions {
+        return Err(format!(
+            "rendered {:?} but the reference is {:?}",
+            dimensions, reference_dimensions
+        )
+        .into());
+    }
+
+    let mut differing = 0;
+    let mut diff = Vec::with_capacity(pixels.len());
+    for (pixel, reference) in pixels.chunks(4).zip(reference.chunks(4)) {
+        if pixel
+            .iter()
+            .zip(reference)
+            .any(|(a, b)| a.abs_diff(*b) > TOLERANCE)
+        {
+            differing += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // the reference dimmed, so the differences stand out
+            let gray = (reference[..3].iter().map(|&c| c as u32).sum::<u32>() / 12) as u8;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    if differing > 0 {
+        let actual_path = output_path(name);
+        let diff_path = output_path(&format!("{}.diff", name));
+        write_png(&actual_path, pixels, dimensions)?;
+        write_png(&diff_path, &diff, dimensions)?;
+        return Err(format!(
+            "{} of {} pixels differ from {} by more than {}, see {} and {}",
+            differing,
+            pixels.len() / 4,
+            reference_path.display(),
+            TOLERANCE,
+            actual_path.display(),
+            diff_path.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn context() -> Result<Arc<RenderContext>> {
+    RenderContext::new(
+        None,
+        &Features::none(),
+        &Features::none(),
+        &InstanceExtensions::none(),
+        &DeviceExtensions {
+            khr_storage_buffer_storage_class: true,
+            ..DeviceExtensions::none()
+        },
+        vec![],
+    )
+    .map_err(|e| format!("golden image tests need a Vulkan device: {}", e).into())
+}
+
+fn phong(
+    context: &RenderContext,
+    renderer: &HeadlessRenderer,
+    parameters: PhongParameters,
+) -> Result<Arc<Phong>> {
+    let (material, future) =
+        Phong::new(parameters, &renderer.material_pipelines(), context.queue())?;
+    future.then_signal_fence_and_flush()?.wait(None)?;
+    Ok(material)
+}
+
+/// An orange cube in front of a tilted blue one, lit from above
+fn scene(context: &RenderContext, renderer: &HeadlessRenderer) -> Result<SceneGraph> {
+    let blue = phong(
+        context,
+        renderer,
+        PhongParameters {
+            ambient: glm::vec3(0.1, 0.4, 0.8),
+            diffuse: glm::vec3(0.1, 0.4, 0.8),
+            shininess: 50.0,
+            ..PhongParameters::default()
+        },
+    )?;
+    let orange = phong(
+        context,
+        renderer,
+        PhongParameters {
+            ambient: glm::vec3(0.8, 0.4, 0.1),
+            diffuse: glm::vec3(0.8, 0.4, 0.1),
+            shininess: 20.0,
+            ..PhongParameters::default()
+        },
+    )?;
+    let cube = Cube::new(context.device());
+
+    let mut scene_graph = SceneGraph::default();
+    scene_graph.add_child(SceneGraph::new(
+        glm::rotate_y(
+            &glm::translate(&glm::identity(), &glm::vec3(-1.0, 0.0, -1.0)),
+            0.6,
+        ),
+        Some(SceneObject::new(context.device(), blue, cube.clone())),
+        vec![],
+    ));
+    scene_graph.add_child(SceneGraph::new(
+        glm::translate(&glm::identity(), &glm::vec3(1.0, -0.5, 1.0)),
+        Some(SceneObject::new(context.device(), orange, cube)),
+        vec![],
+    ));
+    Ok(scene_graph)
+}
+
+/// The view and lighting descriptor sets for `camera`, with `renderer`'s ambient occlusion
+fn view_sets(
+    context: &RenderContext,
+    renderer: &HeadlessRenderer,
+    camera: &Camera,
+    aspect_ratio: f32,
+) -> Result<(
+    Arc<dyn DescriptorSet + Send + Sync>,
+    Arc<dyn DescriptorSet + Send + Sync>,
+)> {
+    let view_uniforms = CpuAccessibleBuffer::from_data(
+        context.device(),
+        BufferUsage::uniform_buffer(),
+        false,
+        ViewUniforms::new(camera, aspect_ratio, &renderer.depth_config()),
+    )?;
+    let view_set = Arc::new(
+        PersistentDescriptorSet::start(renderer.scene_layouts().view())
+            .add_buffer(view_uniforms)?
+            .build()?,
+    );
+
+    let light = PointLight {
+        position: glm::vec3(0.0, 3.0, 2.0),
+        ..PointLight::default()
+    };
+    let background = renderer.clear_color();
+    let lighting_uniforms = CpuAccessibleBuffer::from_data(
+        context.device(),
+        BufferUsage::uniform_buffer(),
+        false,
+        LightingUniforms::new(
+            camera.position(),
+            &light,
+            &Fog::default(),
+            glm::vec3(background[0], background[1], background[2]),
+        ),
+    )?;
+    let lighting_set = Arc::new(
+        PersistentDescriptorSet::start(renderer.scene_layouts().lighting())
+            .add_buffer(lighting_uniforms)?
+            .add_sampled_image(
+                renderer.ambient_occlusion().occlusion(),
+                renderer.ambient_occlusion().sampler(),
+            )?
+            .build()?,
+    );
+
+    Ok((view_set, lighting_set))
+}
+
+/// Renders the scene from each camera in its rect and compares the result with `name`'s reference
+fn render_and_compare(name: &str, cameras: &[(Camera, ViewportRect)]) -> Result<()> {
+    let context = context()?;
+    let renderer = HeadlessRenderer::new(
+        context.clone(),
+        DIMENSIONS,
+        DepthConfig::reverse_z_infinite(0.1),
+    )?;
+    let scene_graph = scene(&context, &renderer)?;
+
+    let mut views = vec![];
+    for (camera, rect) in cameras {
+        let (view_set, lighting_set) =
+            view_sets(&context, &renderer, camera, rect.aspect_ratio(DIMENSIONS))?;
+        views.push(SceneView {
+            rect: *rect,
+            render_queue: RenderQueue::new(&scene_graph, camera.position()),
+            view_set,
+            lighting_set,
+        });
+    }
+
+    let pixels = renderer.render(&views)?;
+    compare_with_reference(name, &pixels, DIMENSIONS)
+}
+
+fn front_camera() -> Camera {
+    Camera::new(
+        glm::vec3(0.0, 1.0, 6.0),
+        glm::vec3(0.0, 1.0, 0.0),
+        -90.0,
+        -10.0,
+    )
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn phong_cubes() -> Result<()> {
+    render_and_compare("phong_cubes", &[(front_camera(), ViewportRect::full())])
+}
+
+#[test]
+#[ignore = "needs a Vulkan device"]
+fn picture_in_picture() -> Result<()> {
+    let [main_rect, inset_rect] = ViewportRect::picture_in_picture(0.3, 0.02);
+    let overhead_camera = Camera::new(
+        glm::vec3(0.0, 8.0, 0.0),
+        glm::vec3(0.0, 1.0, 0.0),
+        -90.0,
+        -89.0,
+    );
+    render_and_compare(
+        "picture_in_picture",
+        &[(front_camera(), main_rect), (overhead_camera, inset_rect)],
+    )
+}